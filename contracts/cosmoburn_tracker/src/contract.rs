@@ -1,15 +1,37 @@
-use crate::functions::check_is_admin;
-use crate::msg::{ExecuteMsg, InstantiateMsg};
+use crate::functions::{
+    adjust_category_supply, adjust_supply, append_anomaly, append_exclusion_event,
+    append_track_failure, append_transfer, apply_writes, balance_drift, bank_balance,
+    check_has_role, check_is_admin, held_balance, import_supply, reset_balance, seed_excluded_supply,
+    StorageCache, SupplyBucket,
+};
+use crate::migrations::run_migrations;
+use crate::msg::{ExecuteMsg, ImportedBalance, InstantiateMsg, MigrateMsg};
+use crate::policy::{
+    check_max_supply, check_not_blocked, check_not_paused, check_transferable, check_unlocked,
+    check_wallet_cap, consume_emission, consume_quota,
+};
 use astroport::asset::validate_native_denom;
 use astroport::tokenfactory_tracker::SudoMsg;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response, StdError, Storage, Uint128};
-use cw2::set_contract_version;
-use std::collections::HashMap;
+use cosmwasm_std::{
+    Addr, Decimal, DepsMut, Env, Event, MessageInfo, Order, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw2::{get_contract_version, set_contract_version};
+use cw_storage_plus::Bound;
+use semver::Version;
 
 use crate::error::ContractError;
-use crate::state::{Config, State, BALANCES, CONFIG, STATE, TOTAL_SUPPLY_HISTORY};
+use crate::state::{
+    Anomaly, Config, DenomConfig, EmissionSchedule, ExclusionEvent, ExclusionInfo, HookMode,
+    PendingAdmin, RateLimit, Role, TrackFailure, Transfer, TransferKind, VestingSchedule,
+    WalletCap, ADDRESS_CATEGORY,
+    ADDRESS_RATE_LIMITS, BALANCES, BALANCE_TWAB, BLOCKED, BURNED, BURN_RANKING, CATEGORIES,
+    CATEGORY_MEMBERS, CONFIG, DENOMS, EMISSION_SCHEDULES, EXCLUDED_WALLETS, IMPORT_SEALED,
+    PAUSED, PAUSE_EXEMPT, PENDING_ADMIN, RATE_LIMIT, RATE_LIMIT_EXEMPT, ROLES, SOULBOUND_PAIRS,
+    TOTAL_BURNED_HISTORY, TOTAL_MINTED_HISTORY, VESTING, WALLET_CAPS, WALLET_CAP_EXEMPT,
+};
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -28,37 +50,136 @@ pub fn instantiate(
     validate_native_denom(&msg.tracked_denom)?;
 
     let config = Config {
-        d: msg.tracked_denom.clone(),
-        m: msg.tokenfactory_module_address,
-        admin_addr: deps.api.addr_validate(&msg.admin_addr)?.to_string(),
+        admin_addr: Some(deps.api.addr_validate(&msg.admin_addr)?.to_string()),
+        hook_mode: HookMode::Block,
     };
 
-    let state = State {
-        excluded_wallets: HashMap::new(),
+    let denom_config = DenomConfig {
+        tracked_denom: msg.tracked_denom.clone(),
+        module_address: msg.tokenfactory_module_address,
+        max_supply: None,
+        soulbound: false,
+        lenient: false,
     };
 
-    STATE.save(deps.storage, &state)?;
-
     CONFIG.save(deps.storage, &config)?;
 
+    DENOMS.save(deps.storage, &denom_config.tracked_denom, &denom_config)?;
+
     Ok(Response::default()
         .add_attribute("action", "instantiate")
         .add_attribute("contract", CONTRACT_NAME)
-        .add_attribute("tracked_denom", config.d)
-        .add_attribute("tokenfactory_module_address", config.m))
+        .add_attribute("tracked_denom", denom_config.tracked_denom)
+        .add_attribute("tokenfactory_module_address", denom_config.module_address))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    if let Some(role) = msg.required_role() {
+        check_has_role(&deps, &info, role)?;
+    }
+
     match msg {
-        ExecuteMsg::ExcludeWallet { addr, memo } => try_exclude_wallet(deps, info, addr, memo),
+        ExecuteMsg::ExcludeWallet { addr, memo } => {
+            try_exclude_wallet(deps, env, info, addr, memo)
+        }
+
+        ExecuteMsg::IncludeWallet { addr } => try_include_wallet(deps, env, info, addr),
+
+        ExecuteMsg::RegisterDenom {
+            denom,
+            tokenfactory_module_address,
+        } => try_register_denom(deps, env, info, denom, tokenfactory_module_address),
+
+        ExecuteMsg::ProposeNewAdmin { new_admin, expiry } => {
+            try_propose_new_admin(deps, env, info, new_admin, expiry)
+        }
+
+        ExecuteMsg::AcceptAdmin {} => try_accept_admin(deps, env, info),
+
+        ExecuteMsg::RenounceAdmin {} => try_renounce_admin(deps, info),
+
+        ExecuteMsg::GrantRole { address, role } => try_grant_role(deps, info, address, role),
+
+        ExecuteMsg::RevokeRole { address, role } => try_revoke_role(deps, info, address, role),
+
+        ExecuteMsg::Pause {} => try_set_paused(deps, true),
+
+        ExecuteMsg::Unpause {} => try_set_paused(deps, false),
+
+        ExecuteMsg::SetPauseExemption { address, exempt } => {
+            try_set_pause_exemption(deps, address, exempt)
+        }
+
+        ExecuteMsg::BlockAddress { address, reason } => {
+            try_block_address(deps, env, address, reason)
+        }
+
+        ExecuteMsg::UnblockAddress { address } => try_unblock_address(deps, env, address),
+
+        ExecuteMsg::SetRateLimit { address, limit } => try_set_rate_limit(deps, address, limit),
+
+        ExecuteMsg::SetRateLimitExemption { address, exempt } => {
+            try_set_rate_limit_exemption(deps, address, exempt)
+        }
+
+        ExecuteMsg::SetMaxSupply { denom, max_supply } => {
+            try_set_max_supply(deps, info, denom, max_supply)
+        }
+
+        ExecuteMsg::SetHookMode { mode } => try_set_hook_mode(deps, info, mode),
+
+        ExecuteMsg::ImportBalances { denom, balances } => {
+            try_import_balances(deps, env, info, denom, balances)
+        }
+
+        ExecuteMsg::SealImport { denom } => try_seal_import(deps, info, denom),
+
+        ExecuteMsg::CreateCategory { category } => try_create_category(deps, info, category),
+
+        ExecuteMsg::SetAddressCategory { address, category } => {
+            try_set_address_category(deps, env, info, address, category)
+        }
+
+        ExecuteMsg::Sync { denom, addresses } => try_sync(deps, env, denom, addresses),
+
+        ExecuteMsg::SetLenient { denom, lenient } => try_set_lenient(deps, info, denom, lenient),
+
+        ExecuteMsg::CreateVesting {
+            denom,
+            address,
+            schedule,
+        } => try_create_vesting(deps, info, denom, address, schedule),
+
+        ExecuteMsg::CancelVesting { denom, address } => {
+            try_cancel_vesting(deps, info, denom, address)
+        }
+
+        ExecuteMsg::SetSoulbound { denom, soulbound } => {
+            try_set_soulbound(deps, info, denom, soulbound)
+        }
 
-        ExecuteMsg::IncludeWallet { addr } => try_include_wallet(deps, info, addr),
+        ExecuteMsg::SetSoulboundPair {
+            denom,
+            from,
+            to,
+            allowed,
+        } => try_set_soulbound_pair(deps, info, denom, from, to, allowed),
+
+        ExecuteMsg::SetEmissionSchedule { denom, schedule } => {
+            try_set_emission_schedule(deps, info, denom, schedule)
+        }
+
+        ExecuteMsg::SetWalletCap { denom, cap } => try_set_wallet_cap(deps, denom, cap),
+
+        ExecuteMsg::SetWalletCapExemption { address, exempt } => {
+            try_set_wallet_cap_exemption(deps, address, exempt)
+        }
     }
 }
 
@@ -66,246 +187,2558 @@ pub fn execute(
 // addr: wallet address to be excluded.
 pub fn try_exclude_wallet(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     addr: String,
     memo: String,
 ) -> Result<Response, ContractError> {
-    check_is_admin(&deps, info)?;
-
-    validate_native_denom(&addr)?;
+    let wallet = deps.api.addr_validate(&addr)?;
 
+    if EXCLUDED_WALLETS.may_load(deps.storage, &wallet)?.is_some() {
+        return Err(ContractError::TokenAlreadyWhitelisted {});
+    }
 
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.excluded_wallets.contains_key(&addr.to_string()) {
-            return Err(ContractError::TokenAlreadyWhitelisted {});
+    // The bank balance, which the tracked one may lag behind, is what the
+    // wallet takes into the excluded supply
+    let block_seconds = env.block.time.seconds();
+    for denom in tracked_denoms(deps.storage)? {
+        let key = (denom.as_str(), wallet.as_str());
+        let tracked = BALANCES.may_load(deps.storage, key)?.unwrap_or_default();
+        if !tracked.is_zero() {
+            reset_balance(
+                deps.storage,
+                block_seconds,
+                &denom,
+                wallet.as_str(),
+                tracked,
+                Uint128::zero(),
+            )?;
         }
+        let balance = bank_balance(&deps.querier, wallet.as_str(), &denom)?;
+        if !balance.is_zero() {
+            adjust_supply(deps.storage, block_seconds, &denom, SupplyBucket::Excluded, balance, true)?;
+        }
+        // Its category counts the bank balance from now on
+        let (drift, into) = if balance > tracked {
+            (balance - tracked, true)
+        } else {
+            (tracked - balance, false)
+        };
+        adjust_category_supply(deps.storage, block_seconds, &denom, wallet.as_str(), drift, into)?;
+    }
 
-        state
-            .excluded_wallets
-            .insert(addr.to_string(), memo);
-        Ok(state)
-    })?;
+    EXCLUDED_WALLETS.save(
+        deps.storage,
+        &wallet,
+        &ExclusionInfo { memo: memo.clone() },
+        block_seconds,
+    )?;
+    append_exclusion_event(
+        deps.storage,
+        ExclusionEvent {
+            id: 0,
+            address: wallet,
+            excluded: true,
+            memo: Some(memo),
+            sender: info.sender,
+            block_time: block_seconds,
+            block_height: env.block.height,
+        },
+    )?;
 
     Ok(Response::new()
         .add_attribute("method", "try_exclude_wallet")
         .add_attribute("addr", addr.clone()))
 }
 
+fn tracked_denoms(storage: &dyn Storage) -> StdResult<Vec<String>> {
+    DENOMS.keys(storage, None, None, Order::Ascending).collect()
+}
+
 // Removes addres from exluded wallets.
 // addr: wallet address to be removed from excluded wallets.
 pub fn try_include_wallet(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     addr: String,
 ) -> Result<Response, ContractError> {
-    check_is_admin(&deps, info)?;
+    let wallet = Addr::unchecked(&addr);
 
-    STATE.update(deps.storage, |mut state| -> Result<_, ContractError> {
-        if state.excluded_wallets.remove(&addr.clone()) == None {
-            return Err(ContractError::TokenNotFound {});
-        };
-        Ok(state)
-    })?;
+    if EXCLUDED_WALLETS.may_load(deps.storage, &wallet)?.is_none() {
+        return Err(ContractError::TokenNotFound {});
+    }
+
+    let block_seconds = env.block.time.seconds();
+    EXCLUDED_WALLETS.remove(deps.storage, &wallet, block_seconds)?;
+    append_exclusion_event(
+        deps.storage,
+        ExclusionEvent {
+            id: 0,
+            address: wallet.clone(),
+            excluded: false,
+            memo: None,
+            sender: info.sender,
+            block_time: block_seconds,
+            block_height: env.block.height,
+        },
+    )?;
+
+    // The wallet's live balance moves back into the supply
+    for denom in tracked_denoms(deps.storage)? {
+        let key = (denom.as_str(), wallet.as_str());
+        let tracked = BALANCES.may_load(deps.storage, key)?.unwrap_or_default();
+        let balance = bank_balance(&deps.querier, wallet.as_str(), &denom)?;
+        if !balance.is_zero() {
+            adjust_supply(deps.storage, block_seconds, &denom, SupplyBucket::Excluded, balance, false)?;
+        }
+        if tracked != balance {
+            reset_balance(
+                deps.storage,
+                block_seconds,
+                &denom,
+                wallet.as_str(),
+                tracked,
+                balance,
+            )?;
+        }
+    }
 
     Ok(Response::new()
         .add_attribute("method", "try_include_wallet")
         .add_attribute("addr", addr.clone()))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
-    match msg {
-        
-        SudoMsg::BlockBeforeSend { from, to, amount } => {
-            let config = CONFIG.load(deps.storage)?;
+// Starts tracking an additional tokenfactory denom.
+// denom: denom to be tracked.
+// tokenfactory_module_address: address that mints and burns the denom.
+pub fn try_register_denom(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    tokenfactory_module_address: String,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
 
-            if amount.denom != config.d {
-                Err(ContractError::InvalidDenom {
-                    expected_denom: config.d,
-                })
-            } else {
+    validate_native_denom(&denom)?;
 
-                track_balances(
-                    deps.storage,
-                    env.block.time.seconds(),
-                    &config,
-                    from,
-                    to,
-                    amount.amount,
-                )
-            }
+    deps.api.addr_validate(&tokenfactory_module_address)?;
+
+    if DENOMS.has(deps.storage, &denom) {
+        return Err(ContractError::DenomAlreadyTracked { denom });
+    }
+
+    let denom_config = DenomConfig {
+        tracked_denom: denom,
+        module_address: tokenfactory_module_address,
+        max_supply: None,
+        soulbound: false,
+        lenient: false,
+    };
+
+    DENOMS.save(deps.storage, &denom_config.tracked_denom, &denom_config)?;
+
+    // Excluded wallets may already hold the denom, minted before it was tracked
+    let block_seconds = env.block.time.seconds();
+    seed_excluded_supply(deps.storage, &deps.querier, block_seconds, &denom_config.tracked_denom)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_register_denom")
+        .add_attribute("tracked_denom", denom_config.tracked_denom)
+        .add_attribute("tokenfactory_module_address", denom_config.module_address))
+}
+
+// Proposes a new admin, who has to accept before taking over.
+// new_admin: address of the proposed admin.
+// expiry: block time in seconds after which the proposal lapses.
+pub fn try_propose_new_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    new_admin: String,
+    expiry: Option<u64>,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+
+    if let Some(expires_at) = expiry {
+        if expires_at <= env.block.time.seconds() {
+            return Err(ContractError::AdminProposalExpired {});
         }
+    }
 
-        SudoMsg::TrackBeforeSend { .. } => Ok(Response::default()),
+    PENDING_ADMIN.save(
+        deps.storage,
+        &PendingAdmin {
+            addr: new_admin.to_string(),
+            expires_at: expiry,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_propose_new_admin")
+        .add_attribute("new_admin", new_admin))
+}
+
+// Makes the pending admin the current admin.
+pub fn try_accept_admin(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+
+    if info.sender != pending.addr {
+        return Err(ContractError::Unauthorized {});
     }
+
+    if let Some(expires_at) = pending.expires_at {
+        if expires_at <= env.block.time.seconds() {
+            return Err(ContractError::AdminProposalExpired {});
+        }
+    }
+
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.admin_addr = Some(pending.addr.clone());
+        Ok(config)
+    })?;
+
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("method", "try_accept_admin")
+        .add_attribute("admin", pending.addr))
 }
 
+// Gives up the admin role for good, leaving the contract without an admin.
+pub fn try_renounce_admin(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
 
-pub fn track_balances(
-    storage: &mut dyn Storage,
-    block_seconds: u64,
-    config: &Config,
-    from: String,
-    to: String,
-    amount: Uint128,
+    CONFIG.update(deps.storage, |mut config| -> Result<_, ContractError> {
+        config.admin_addr = None;
+        Ok(config)
+    })?;
+
+    PENDING_ADMIN.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("method", "try_renounce_admin"))
+}
+
+// Grants a role to an operator.
+// address: operator receiving the role.
+// role: role to be granted.
+pub fn try_grant_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
 ) -> Result<Response, ContractError> {
-    // If the token is minted directly to an address, we don't need to subtract
-    // as the sender is the module address
-    let state = STATE.load(storage)?;
+    check_is_admin(&deps, info)?;
 
-    let is_excluded_from = state.excluded_wallets.contains_key(&from);
-    let is_excluded_to = state.excluded_wallets.contains_key(&to);
+    let address = deps.api.addr_validate(&address)?;
 
-    if from.ne(&config.m) && !is_excluded_from {
-        BALANCES.update::<_, StdError>(storage, &from, block_seconds, |balance| {
-            balance
-                .unwrap_or_default()
-                .checked_sub(amount)
-                .map_err(|err| {
-                    StdError::generic_err(format!(
-                        "{err}: send from {from} to {to} amount {amount} block_seconds {block_seconds}"
-                    ))
-                })
-        })?;
-    } else {
-        // Minted new tokens or excluded wallet
-        TOTAL_SUPPLY_HISTORY.update::<_, StdError>(storage, block_seconds, |balance| {
-            Ok(balance.unwrap_or_default().checked_add(amount)?)
-        })?;
+    ROLES.save(deps.storage, (&address, role.as_str()), &role)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_grant_role")
+        .add_attribute("address", address)
+        .add_attribute("role", role.as_str()))
+}
+
+// Revokes a role from an operator.
+// address: operator losing the role.
+// role: role to be revoked.
+pub fn try_revoke_role(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    role: Role,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    let address = Addr::unchecked(address);
+
+    if !ROLES.has(deps.storage, (&address, role.as_str())) {
+        return Err(ContractError::RoleNotGranted {});
     }
 
-    // When burning tokens, the receiver is the token factory module address
-    // Sending tokens to the module address isn't allowed by the chain
-    if to.ne(&config.m) && !is_excluded_to {
-        BALANCES.update::<_, StdError>(storage, &to, block_seconds, |balance| {
-            Ok(balance.unwrap_or_default().checked_add(amount)?)
-        })?;
+    ROLES.remove(deps.storage, (&address, role.as_str()));
+
+    Ok(Response::new()
+        .add_attribute("method", "try_revoke_role")
+        .add_attribute("address", address)
+        .add_attribute("role", role.as_str()))
+}
+
+// Pauses or resumes sends of all tracked denoms.
+// paused: whether the before-send hook should reject sends.
+pub fn try_set_paused(deps: DepsMut, paused: bool) -> Result<Response, ContractError> {
+    PAUSED.save(deps.storage, &paused)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_paused")
+        .add_attribute("paused", paused.to_string()))
+}
+
+// Lets an address keep sending and receiving while paused.
+// address: address to be exempted or no longer exempted.
+// exempt: whether the address is exempt.
+pub fn try_set_pause_exemption(
+    deps: DepsMut,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+
+    if exempt {
+        PAUSE_EXEMPT.save(deps.storage, &address, &())?;
     } else {
-        // Burned tokens or sent to excluded wallet
-        TOTAL_SUPPLY_HISTORY.update::<_, StdError>(storage, block_seconds, |balance| {
-            balance
-                .unwrap_or_default()
-                .checked_sub(amount)
-                .map_err(|err| {
-                    StdError::generic_err(format!(
-                        "{err}: from {from} to {to} amount {amount} block_seconds {block_seconds}"
-                    ))
-                })
-        })?;
+        PAUSE_EXEMPT.remove(deps.storage, &address);
     }
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attribute("method", "try_set_pause_exemption")
+        .add_attribute("address", address)
+        .add_attribute("exempt", exempt.to_string()))
 }
 
+// Freezes an address, rejecting its sends and receipts except burns.
+// address: address to be blocked.
+// reason: why the address was blocked.
+pub fn try_block_address(
+    deps: DepsMut,
+    env: Env,
+    address: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{
-        mock_dependencies_with_balance, mock_env, mock_info, MockApi, MockQuerier, MockStorage
-    };
-    use cosmwasm_std::{coins};
-    use cosmwasm_std::OwnedDeps;
-    use cosmwasm_std::{Addr, Uint128};
+    BLOCKED.save(deps.storage, &address, &reason, env.block.time.seconds())?;
 
-    const USER: &str = "neutron1";
-    const ADMIN: &str = "neutron2";
-    const TOKEN_FACTORY: &str = "neutron4";
-    const NATIVE_DENOM: &str = "untrn";
+    Ok(Response::new()
+        .add_attribute("method", "try_block_address")
+        .add_attribute("address", address)
+        .add_attribute("reason", reason))
+}
 
-    fn proper_initialization() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
-        let mut deps = mock_dependencies_with_balance(&coins(2, NATIVE_DENOM));
+// Lifts the freeze on an address.
+// address: address to be unblocked.
+pub fn try_unblock_address(
+    deps: DepsMut,
+    env: Env,
+    address: String,
+) -> Result<Response, ContractError> {
+    let address = Addr::unchecked(address);
 
-        let msg = InstantiateMsg {
-            admin_addr: Addr::unchecked(ADMIN).to_string(),
-            tokenfactory_module_address: Addr::unchecked(TOKEN_FACTORY).to_string(),
-            tracked_denom: NATIVE_DENOM.to_string(),
-        };
+    if BLOCKED.may_load(deps.storage, &address)?.is_none() {
+        return Err(ContractError::TokenNotFound {});
+    }
 
-        let info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+    BLOCKED.remove(deps.storage, &address, env.block.time.seconds())?;
 
-        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
-        assert_eq!(0, res.messages.len());
+    Ok(Response::new()
+        .add_attribute("method", "try_unblock_address")
+        .add_attribute("address", address))
+}
 
-        deps
+// Sets or removes a rate limit.
+// address: address the limit applies to, or none for the global limit.
+// limit: limit to be set, or none to remove it.
+pub fn try_set_rate_limit(
+    deps: DepsMut,
+    address: Option<String>,
+    limit: Option<RateLimit>,
+) -> Result<Response, ContractError> {
+    if let Some(limit) = &limit {
+        if limit.quota.is_some() && limit.window == 0 {
+            return Err(ContractError::InvalidRateLimit {});
+        }
     }
 
-    #[test]
-    fn track_mint() {
-        let mut deps = proper_initialization();
-        let _info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+    let address = address
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?;
 
-        let config = Config {
-            d: NATIVE_DENOM.to_string(),
-            m: TOKEN_FACTORY.to_string(),
-            admin_addr: ADMIN.to_string(),
-        };
-        
-        let _res:Result<Response, ContractError> = track_balances(
-            &mut deps.storage,
-            mock_env().block.time.seconds(),
-            &config,
-            TOKEN_FACTORY.to_string(),
-            USER.to_string(),
-            Uint128::new(999),
-        );
-        
-        assert_eq!(TOTAL_SUPPLY_HISTORY.load(&mut deps.storage).unwrap(), Uint128::new(999));
+    match (&address, &limit) {
+        (Some(address), Some(limit)) => ADDRESS_RATE_LIMITS.save(deps.storage, address, limit)?,
+        (Some(address), None) => ADDRESS_RATE_LIMITS.remove(deps.storage, address),
+        (None, Some(limit)) => RATE_LIMIT.save(deps.storage, limit)?,
+        (None, None) => RATE_LIMIT.remove(deps.storage),
     }
 
-    #[test]
-    fn track_burn() {
-        let mut deps = proper_initialization();
-        let _info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+    Ok(Response::new()
+        .add_attribute("method", "try_set_rate_limit")
+        .add_attribute(
+            "address",
+            address.map_or_else(|| "global".to_string(), String::from),
+        )
+        .add_attribute("removed", limit.is_none().to_string()))
+}
 
-        let config = Config {
-            d: NATIVE_DENOM.to_string(),
-            m: TOKEN_FACTORY.to_string(),
-            admin_addr: ADMIN.to_string(),
-        };
-        
-        let _res:Result<Response, ContractError> = track_balances(
-            &mut deps.storage,
-            mock_env().block.time.seconds(),
-            &config,
-            TOKEN_FACTORY.to_string(),
-            USER.to_string(),
-            Uint128::new(100),
-        );
-        
-        let _res:Result<Response, ContractError> = track_balances(
-            &mut deps.storage,
-            mock_env().block.time.seconds(),
-            &config,
-            USER.to_string(),
-            TOKEN_FACTORY.to_string(),
-            Uint128::new(10),
-        );
-        
-        assert_eq!(TOTAL_SUPPLY_HISTORY.load(&mut deps.storage).unwrap(), Uint128::new(90));
+// Lets an address send without being rate limited.
+// address: address to be exempted or no longer exempted.
+// exempt: whether the address is exempt.
+pub fn try_set_rate_limit_exemption(
+    deps: DepsMut,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+
+    if exempt {
+        RATE_LIMIT_EXEMPT.save(deps.storage, &address, &())?;
+    } else {
+        RATE_LIMIT_EXEMPT.remove(deps.storage, &address);
     }
 
-    #[test]
-    fn track_balance() {
-        let mut deps = proper_initialization();
-        let _info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+    Ok(Response::new()
+        .add_attribute("method", "try_set_rate_limit_exemption")
+        .add_attribute("address", address)
+        .add_attribute("exempt", exempt.to_string()))
+}
 
-        let config = Config {
-            d: NATIVE_DENOM.to_string(),
-            m: TOKEN_FACTORY.to_string(),
-            admin_addr: ADMIN.to_string(),
-        };
-        
-        let _res:Result<Response, ContractError> = track_balances(
-            &mut deps.storage,
-            mock_env().block.time.seconds(),
-            &config,
-            TOKEN_FACTORY.to_string(),
-            USER.to_string(),
-            Uint128::new(100),
+// Caps the minted supply of a denom.
+// denom: tracked denom the cap applies to.
+// max_supply: new cap, no higher than the current one.
+pub fn try_set_max_supply(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    max_supply: Uint128,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    let mut denom_config = DENOMS
+        .may_load(deps.storage, &denom)?
+        .ok_or(ContractError::DenomNotTracked { denom })?;
+
+    if denom_config.max_supply.is_some_and(|current| max_supply > current) {
+        return Err(ContractError::CannotRaiseMaxSupply {});
+    }
+
+    denom_config.max_supply = Some(max_supply);
+    DENOMS.save(deps.storage, &denom_config.tracked_denom, &denom_config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_max_supply")
+        .add_attribute("denom", denom_config.tracked_denom)
+        .add_attribute("max_supply", max_supply))
+}
+
+// Moves the accounting between the blocking and the tracking before-send hook.
+// mode: hook to run the accounting in.
+pub fn try_set_hook_mode(
+    deps: DepsMut,
+    info: MessageInfo,
+    mode: HookMode,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    CONFIG.update::<_, StdError>(deps.storage, |mut config| {
+        config.hook_mode = mode;
+        Ok(config)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_hook_mode")
+        .add_attribute("mode", mode.as_str()))
+}
+
+// Seeds balances of holders from before the hook was registered.
+// denom: tracked denom to be imported.
+// balances: batch of current balances, each with optional earlier checkpoints.
+pub fn try_import_balances(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    balances: Vec<ImportedBalance>,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    let config = DENOMS
+        .may_load(deps.storage, &denom)?
+        .ok_or(ContractError::DenomNotTracked { denom })?;
+    if IMPORT_SEALED.has(deps.storage, &config.tracked_denom) {
+        return Err(ContractError::ImportSealed {
+            denom: config.tracked_denom,
+        });
+    }
+
+    let block_seconds = env.block.time.seconds();
+    let mut imported = Uint128::zero();
+    // Balance changes of the batch as (timestamp, previous, balance)
+    let mut changes: Vec<(u64, Uint128, Uint128)> = vec![];
+
+    for entry in &balances {
+        let address = deps.api.addr_validate(&entry.address)?;
+        let invalid = |reason: &str| ContractError::InvalidImport {
+            address: entry.address.clone(),
+            reason: reason.to_string(),
+        };
+
+        if address.as_str() == config.module_address
+            || EXCLUDED_WALLETS.may_load(deps.storage, &address)?.is_some()
+        {
+            return Err(invalid("address isn't tracked"));
+        }
+        let key = (config.tracked_denom.as_str(), address.as_str());
+        let first = entry.checkpoints.first().map_or(block_seconds, |(timestamp, _)| *timestamp);
+        let changed_since = BALANCES
+            .changelog()
+            .prefix(key)
+            .keys(deps.storage, Some(Bound::inclusive(first)), None, Order::Ascending)
+            .next()
+            .is_some();
+        if changed_since
+            || BALANCES.may_load(deps.storage, key)?.is_some()
+            || BALANCE_TWAB.may_load(deps.storage, key)?.is_some()
+        {
+            return Err(invalid("balance is already tracked"));
+        }
+        let in_order = entry
+            .checkpoints
+            .iter()
+            .map(|(timestamp, _)| *timestamp)
+            .chain([block_seconds])
+            .collect::<Vec<_>>()
+            .windows(2)
+            .all(|pair| pair[0] < pair[1]);
+        if !in_order {
+            return Err(invalid("checkpoints must be ascending and in the past"));
+        }
+
+        let bank = bank_balance(&deps.querier, address.as_str(), &config.tracked_denom)?;
+        if entry.amount != bank {
+            return Err(ContractError::ImportMismatch {
+                address: entry.address.clone(),
+                bank,
+            });
+        }
+
+        let mut previous = Uint128::zero();
+        for (timestamp, amount) in entry.checkpoints.iter().chain([(block_seconds, entry.amount)].iter()) {
+            BALANCES.save(deps.storage, key, amount, *timestamp)?;
+            BALANCE_TWAB.update::<_, StdError>(deps.storage, key, *timestamp, |twab| {
+                twab.unwrap_or_default().advance(*timestamp, *amount)
+            })?;
+            changes.push((*timestamp, previous, *amount));
+            previous = *amount;
+        }
+        // Categories count the imported balance from the import on, as they
+        // count what an address holds from when it's assigned
+        adjust_category_supply(deps.storage, block_seconds, &config.tracked_denom, address.as_str(), entry.amount, true)?;
+
+        imported = imported.checked_add(entry.amount).map_err(StdError::from)?;
+    }
+
+    // Checkpoints move the supply too, including history written since them
+    import_supply(deps.storage, &config.tracked_denom, changes)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_import_balances")
+        .add_attribute("denom", config.tracked_denom)
+        .add_attribute("count", balances.len().to_string())
+        .add_attribute("imported", imported))
+}
+
+// Closes the balance import of a denom, after which it can't be run again.
+// denom: tracked denom whose import is done.
+pub fn try_seal_import(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    if !DENOMS.has(deps.storage, &denom) {
+        return Err(ContractError::DenomNotTracked { denom });
+    }
+    if IMPORT_SEALED.has(deps.storage, &denom) {
+        return Err(ContractError::ImportSealed { denom });
+    }
+
+    IMPORT_SEALED.save(deps.storage, &denom, &())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_seal_import")
+        .add_attribute("denom", denom))
+}
+
+// Adds an address category.
+// category: name of the category, such as treasury or team.
+pub fn try_create_category(
+    deps: DepsMut,
+    info: MessageInfo,
+    category: String,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    if CATEGORIES.has(deps.storage, &category) {
+        return Err(ContractError::CategoryAlreadyExists { category });
+    }
+
+    CATEGORIES.save(deps.storage, &category, &())?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_create_category")
+        .add_attribute("category", category))
+}
+
+// Moves an address into a category, carrying its balances over.
+// address: address to be categorized.
+// category: category to put the address in, or none to take it out.
+pub fn try_set_address_category(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+    category: Option<String>,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    if let Some(category) = &category {
+        if !CATEGORIES.has(deps.storage, category) {
+            return Err(ContractError::CategoryNotFound {
+                category: category.clone(),
+            });
+        }
+    }
+
+    // Category totals count what the address is accounted with, excluded or not
+    let block_seconds = env.block.time.seconds();
+    let balances = tracked_denoms(deps.storage)?
+        .into_iter()
+        .map(|denom| {
+            let balance = held_balance(deps.storage, &deps.querier, &denom, address.as_str())?;
+            Ok((denom, balance))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    if let Some(previous) = ADDRESS_CATEGORY.may_load(deps.storage, &address)? {
+        for (denom, balance) in &balances {
+            adjust_category_supply(
+                deps.storage,
+                block_seconds,
+                denom,
+                address.as_str(),
+                *balance,
+                false,
+            )?;
+        }
+        CATEGORY_MEMBERS.remove(deps.storage, (&previous, &address));
+        ADDRESS_CATEGORY.remove(deps.storage, &address);
+    }
+
+    if let Some(category) = &category {
+        ADDRESS_CATEGORY.save(deps.storage, &address, category)?;
+        CATEGORY_MEMBERS.save(deps.storage, (category, &address), &())?;
+        for (denom, balance) in &balances {
+            adjust_category_supply(
+                deps.storage,
+                block_seconds,
+                denom,
+                address.as_str(),
+                *balance,
+                true,
+            )?;
+        }
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_address_category")
+        .add_attribute("address", address)
+        .add_attribute("category", category.unwrap_or_default()))
+}
+
+// Resets tracked balances to the bank module's, moving the total supply by the difference.
+// denom: tracked denom to be synced.
+// addresses: holders to be synced.
+pub fn try_sync(
+    deps: DepsMut,
+    env: Env,
+    denom: String,
+    addresses: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = DENOMS
+        .may_load(deps.storage, &denom)?
+        .ok_or(ContractError::DenomNotTracked { denom })?;
+    let block_seconds = env.block.time.seconds();
+
+    let mut response = Response::new()
+        .add_attribute("method", "try_sync")
+        .add_attribute("denom", &config.tracked_denom);
+
+    for address in addresses {
+        let address = deps.api.addr_validate(&address)?;
+        let (tracked, bank) = match balance_drift(deps.as_ref(), &config, &address)? {
+            Some(drift) => drift,
+            None => continue,
+        };
+
+        reset_balance(
+            deps.storage,
+            block_seconds,
+            &config.tracked_denom,
+            address.as_str(),
+            tracked,
+            bank,
+        )?;
+        let (drift, into) = if bank > tracked {
+            (bank - tracked, true)
+        } else {
+            (tracked - bank, false)
+        };
+        adjust_category_supply(deps.storage, block_seconds, &config.tracked_denom, address.as_str(), drift, into)?;
+
+        response = response.add_event(
+            Event::new("balance_sync")
+                .add_attribute("denom", &config.tracked_denom)
+                .add_attribute("address", address)
+                .add_attribute("tracked", tracked)
+                .add_attribute("bank", bank),
         );
+    }
+
+    Ok(response)
+}
+
+// Turns lenient underflow handling on or off for a denom.
+// denom: tracked denom to be updated.
+// lenient: whether sender underflows are clamped and recorded.
+pub fn try_set_lenient(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    lenient: bool,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    let mut denom_config = DENOMS
+        .may_load(deps.storage, &denom)?
+        .ok_or(ContractError::DenomNotTracked { denom })?;
+
+    denom_config.lenient = lenient;
+    DENOMS.save(deps.storage, &denom_config.tracked_denom, &denom_config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_lenient")
+        .add_attribute("denom", denom_config.tracked_denom)
+        .add_attribute("lenient", lenient.to_string()))
+}
+
+// Locks part of an address' balance until it vests.
+// denom: tracked denom to be locked.
+// address: holder of the locked balance.
+// schedule: cliff and linear vesting of the locked amount.
+pub fn try_create_vesting(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    address: String,
+    schedule: VestingSchedule,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    if !DENOMS.has(deps.storage, &denom) {
+        return Err(ContractError::DenomNotTracked { denom });
+    }
+
+    if schedule.start > schedule.cliff
+        || schedule.cliff > schedule.end
+        || schedule.start >= schedule.end
+    {
+        return Err(ContractError::InvalidVestingSchedule {});
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    VESTING.save(deps.storage, (&denom, address.as_str()), &schedule)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_create_vesting")
+        .add_attribute("denom", denom)
+        .add_attribute("address", address)
+        .add_attribute("total", schedule.total))
+}
+
+// Releases whatever is still locked for an address.
+// denom: tracked denom of the schedule.
+// address: holder of the locked balance.
+pub fn try_cancel_vesting(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    if !VESTING.has(deps.storage, (&denom, &address)) {
+        return Err(ContractError::TokenNotFound {});
+    }
+
+    VESTING.remove(deps.storage, (&denom, &address));
+
+    Ok(Response::new()
+        .add_attribute("method", "try_cancel_vesting")
+        .add_attribute("denom", denom)
+        .add_attribute("address", address))
+}
+
+// Turns soulbound mode on or off for a denom.
+// denom: tracked denom to be updated.
+// soulbound: whether the denom can only be minted and burned.
+pub fn try_set_soulbound(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    soulbound: bool,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    let mut denom_config = DENOMS
+        .may_load(deps.storage, &denom)?
+        .ok_or(ContractError::DenomNotTracked { denom })?;
+
+    denom_config.soulbound = soulbound;
+    DENOMS.save(deps.storage, &denom_config.tracked_denom, &denom_config)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_soulbound")
+        .add_attribute("denom", denom_config.tracked_denom)
+        .add_attribute("soulbound", soulbound.to_string()))
+}
+
+// Allows or disallows transfers of a soulbound denom between two addresses.
+// denom: tracked denom the pair applies to.
+// from: sending address.
+// to: receiving address.
+// allowed: whether the pair can transfer.
+pub fn try_set_soulbound_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    from: String,
+    to: String,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    if !DENOMS.has(deps.storage, &denom) {
+        return Err(ContractError::DenomNotTracked { denom });
+    }
+
+    let from = deps.api.addr_validate(&from)?;
+    let to = deps.api.addr_validate(&to)?;
+
+    if allowed {
+        SOULBOUND_PAIRS.save(deps.storage, (&denom, from.as_str(), to.as_str()), &())?;
+    } else {
+        SOULBOUND_PAIRS.remove(deps.storage, (&denom, from.as_str(), to.as_str()));
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_soulbound_pair")
+        .add_attribute("denom", denom)
+        .add_attribute("from", from)
+        .add_attribute("to", to)
+        .add_attribute("allowed", allowed.to_string()))
+}
+
+// Sets or removes the emission schedule of a denom.
+// denom: tracked denom the schedule applies to.
+// schedule: schedule to be enforced, or none to remove it.
+pub fn try_set_emission_schedule(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    schedule: Option<EmissionSchedule>,
+) -> Result<Response, ContractError> {
+    check_is_admin(&deps, info)?;
+
+    if !DENOMS.has(deps.storage, &denom) {
+        return Err(ContractError::DenomNotTracked { denom });
+    }
+
+    match &schedule {
+        Some(schedule) => {
+            if schedule.epoch_length == 0 || schedule.decay > Decimal::one() {
+                return Err(ContractError::InvalidEmissionSchedule {});
+            }
+            EMISSION_SCHEDULES.save(deps.storage, &denom, schedule)?;
+        }
+        None => EMISSION_SCHEDULES.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_emission_schedule")
+        .add_attribute("denom", denom)
+        .add_attribute("removed", schedule.is_none().to_string()))
+}
+
+// Sets or removes the wallet cap of a denom.
+// denom: tracked denom the cap applies to.
+// cap: cap and its schedule, or none to remove it.
+pub fn try_set_wallet_cap(
+    deps: DepsMut,
+    denom: String,
+    cap: Option<WalletCap>,
+) -> Result<Response, ContractError> {
+    if !DENOMS.has(deps.storage, &denom) {
+        return Err(ContractError::DenomNotTracked { denom });
+    }
+
+    match &cap {
+        Some(cap) => {
+            if cap.schedule.windows(2).any(|steps| steps[0].starts_at >= steps[1].starts_at) {
+                return Err(ContractError::InvalidWalletCap {});
+            }
+            WALLET_CAPS.save(deps.storage, &denom, cap)?;
+        }
+        None => WALLET_CAPS.remove(deps.storage, &denom),
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_wallet_cap")
+        .add_attribute("denom", denom)
+        .add_attribute("removed", cap.is_none().to_string()))
+}
+
+// Lets an address, typically a pool, receive past the wallet cap.
+// address: address to be exempted or no longer exempted.
+// exempt: whether the address is exempt.
+pub fn try_set_wallet_cap_exemption(
+    deps: DepsMut,
+    address: String,
+    exempt: bool,
+) -> Result<Response, ContractError> {
+    let address = deps.api.addr_validate(&address)?;
+
+    if exempt {
+        WALLET_CAP_EXEMPT.save(deps.storage, &address, &())?;
+    } else {
+        WALLET_CAP_EXEMPT.remove(deps.storage, &address);
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "try_set_wallet_cap_exemption")
+        .add_attribute("address", address)
+        .add_attribute("exempt", exempt.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+
+    let cannot_migrate = || ContractError::CannotMigrate {
+        contract: stored.contract.clone(),
+        version: stored.version.clone(),
+    };
+
+    if stored.contract != CONTRACT_NAME {
+        return Err(cannot_migrate());
+    }
+
+    let from = Version::parse(&stored.version).map_err(|_| cannot_migrate())?;
+    let to = Version::parse(CONTRACT_VERSION).map_err(|_| cannot_migrate())?;
+
+    if from > to {
+        return Err(cannot_migrate());
+    }
+
+    let applied = run_migrations(deps.branch(), &env, &from)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::default()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION)
+        .add_attribute("migrations", applied.join(",")))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
         
-        assert_eq!(BALANCES.may_load(&deps.storage, &USER).unwrap(), Some(Uint128::new(100)));
+        SudoMsg::BlockBeforeSend { from, to, amount } => {
+            match DENOMS.may_load(deps.storage, &amount.denom)? {
+                None => Err(ContractError::DenomNotTracked {
+                    denom: amount.denom,
+                }),
+                Some(config) => {
+                    check_not_paused(deps.storage, &from, &to)?;
+                    check_not_blocked(deps.storage, &config, &from, &to)?;
+                    check_transferable(deps.storage, &config, &from, &to)?;
+                    check_unlocked(
+                        deps.storage,
+                        &deps.querier,
+                        env.block.time.seconds(),
+                        &config,
+                        &from,
+                        amount.amount,
+                    )?;
+                    consume_quota(
+                        deps.storage,
+                        env.block.time.seconds(),
+                        &config,
+                        &from,
+                        &to,
+                        amount.amount,
+                    )?;
+                    check_max_supply(&deps.querier, &config, &from, &to)?;
+                    consume_emission(
+                        deps.storage,
+                        env.block.time.seconds(),
+                        &config,
+                        &from,
+                        &to,
+                        amount.amount,
+                    )?;
+                    check_wallet_cap(
+                        deps.storage,
+                        env.block.time.seconds(),
+                        &config,
+                        &to,
+                        amount.amount,
+                    )?;
+
+                    if CONFIG.load(deps.storage)?.hook_mode == HookMode::Track {
+                        return Ok(Response::default());
+                    }
+
+                    track_balances(
+                        deps.storage,
+                        env.block.time.seconds(),
+                        env.block.height,
+                        &config,
+                        from,
+                        to,
+                        amount.amount,
+                    )
+                }
+            }
+        }
+
+        SudoMsg::TrackBeforeSend { from, to, amount } => {
+            if CONFIG.load(deps.storage)?.hook_mode != HookMode::Track {
+                return Ok(Response::default());
+            }
+
+            match DENOMS.may_load(deps.storage, &amount.denom)? {
+                None => Ok(Response::default()),
+                Some(config) => track_or_record(
+                    deps.storage,
+                    env.block.time.seconds(),
+                    env.block.height,
+                    &config,
+                    from,
+                    to,
+                    amount.amount,
+                ),
+            }
+        }
+    }
+}
+
+/// Runs the accounting on top of a cache so a failure leaves no partial
+/// writes behind, and records the failure instead of returning it.
+fn track_or_record(
+    storage: &mut dyn Storage,
+    block_seconds: u64,
+    block_height: u64,
+    config: &DenomConfig,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut cache = StorageCache::new(storage);
+    let result = track_balances(
+        &mut cache,
+        block_seconds,
+        block_height,
+        config,
+        from.clone(),
+        to.clone(),
+        amount,
+    );
+    let writes = cache.into_writes();
+
+    match result {
+        Ok(response) => {
+            apply_writes(storage, writes);
+            Ok(response)
+        }
+        Err(err) => {
+            let id = append_track_failure(
+                storage,
+                TrackFailure {
+                    id: 0,
+                    denom: config.tracked_denom.clone(),
+                    from,
+                    to,
+                    amount,
+                    block_time: block_seconds,
+                    block_height,
+                    error: err.to_string(),
+                },
+            )?;
+
+            Ok(Response::new()
+                .add_attribute("method", "track_failed")
+                .add_attribute("failure_id", id.to_string()))
+        }
     }
+}
 
-}
\ No newline at end of file
+
+pub fn track_balances(
+    storage: &mut dyn Storage,
+    block_seconds: u64,
+    block_height: u64,
+    config: &DenomConfig,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // If the token is minted directly to an address, we don't need to subtract
+    // as the sender is the module address
+    let is_excluded_from = EXCLUDED_WALLETS.may_load(storage, &Addr::unchecked(&from))?.is_some();
+    let is_excluded_to = EXCLUDED_WALLETS.may_load(storage, &Addr::unchecked(&to))?.is_some();
+
+    let mut response = Response::default();
+    let mut debited = amount;
+
+    if from.ne(&config.module_address) && !is_excluded_from {
+        let mut underflowed = None;
+        let balance = BALANCES.update::<_, StdError>(storage, (&config.tracked_denom, &from), block_seconds, |balance| {
+            let balance = balance.unwrap_or_default();
+            match balance.checked_sub(amount) {
+                Ok(balance) => Ok(balance),
+                // Lenient denoms clamp the balance rather than freezing the token
+                Err(_) if config.lenient => {
+                    underflowed = Some(balance);
+                    Ok(Uint128::zero())
+                }
+                Err(err) => Err(StdError::generic_err(format!(
+                    "{err}: send from {from} to {to} amount {amount} block_seconds {block_seconds}"
+                ))),
+            }
+        })?;
+        if let Some(tracked) = underflowed {
+            // The sender held more than was tracked, so the untracked part
+            // joins the circulating supply before it moves on
+            let shortfall = amount - tracked;
+            adjust_supply(storage, block_seconds, &config.tracked_denom, SupplyBucket::Circulating, shortfall, true)?;
+            debited = tracked;
+
+            let id = append_anomaly(
+                storage,
+                Anomaly {
+                    id: 0,
+                    denom: config.tracked_denom.clone(),
+                    from: from.clone(),
+                    to: to.clone(),
+                    amount,
+                    shortfall,
+                    tracked,
+                    block_time: block_seconds,
+                    block_height,
+                },
+            )?;
+            response = response.add_event(
+                Event::new("balance_anomaly")
+                    .add_attribute("anomaly_id", id.to_string())
+                    .add_attribute("denom", &config.tracked_denom)
+                    .add_attribute("from", &from)
+                    .add_attribute("shortfall", shortfall)
+                    .add_attribute("tracked", tracked),
+            );
+        }
+        BALANCE_TWAB.update::<_, StdError>(storage, (&config.tracked_denom, &from), block_seconds, |twab| {
+            twab.unwrap_or_default().advance(block_seconds, balance)
+        })?;
+    }
+
+    // When burning tokens, the receiver is the token factory module address
+    // Sending tokens to the module address isn't allowed by the chain
+    if to.ne(&config.module_address) && !is_excluded_to {
+        let balance = BALANCES.update::<_, StdError>(storage, (&config.tracked_denom, &to), block_seconds, |balance| {
+            Ok(balance.unwrap_or_default().checked_add(amount)?)
+        })?;
+        BALANCE_TWAB.update::<_, StdError>(storage, (&config.tracked_denom, &to), block_seconds, |twab| {
+            twab.unwrap_or_default().advance(block_seconds, balance)
+        })?;
+    }
+
+    // Mints and burns grow and shrink the supply, while sends between tracked
+    // and excluded wallets move it from one bucket to the other
+    let bucket = |address: &str, is_excluded: bool| {
+        if address == config.module_address {
+            None
+        } else if is_excluded {
+            Some(SupplyBucket::Excluded)
+        } else {
+            Some(SupplyBucket::Circulating)
+        }
+    };
+    let (source, destination) = (bucket(&from, is_excluded_from), bucket(&to, is_excluded_to));
+    if source != destination {
+        if let Some(source) = source {
+            adjust_supply(storage, block_seconds, &config.tracked_denom, source, amount, false)
+                .map_err(|err| {
+                    StdError::generic_err(format!(
+                        "{err}: from {from} to {to} amount {amount} block_seconds {block_seconds}"
+                    ))
+                })?;
+        }
+        if let Some(destination) = destination {
+            adjust_supply(storage, block_seconds, &config.tracked_denom, destination, amount, true)?;
+        }
+    }
+
+    if from.eq(&config.module_address) {
+        TOTAL_MINTED_HISTORY.update::<_, StdError>(storage, &config.tracked_denom, block_seconds, |minted| {
+            Ok(minted.unwrap_or_default().checked_add(amount)?)
+        })?;
+    }
+
+    adjust_category_supply(storage, block_seconds, &config.tracked_denom, &from, debited, false)?;
+    adjust_category_supply(storage, block_seconds, &config.tracked_denom, &to, amount, true)?;
+
+    // Burned tokens are credited to the sender's burn ledger
+    if to.eq(&config.module_address) && from.ne(&config.module_address) {
+        let burned = BURNED.update::<_, StdError>(storage, (&config.tracked_denom, &from), block_seconds, |burned| {
+            Ok(burned.unwrap_or_default().checked_add(amount)?)
+        })?;
+        BURN_RANKING.remove(storage, (&config.tracked_denom, (burned - amount).u128(), &from));
+        BURN_RANKING.save(storage, (&config.tracked_denom, burned.u128(), &from), &())?;
+        TOTAL_BURNED_HISTORY.update::<_, StdError>(storage, &config.tracked_denom, block_seconds, |burned| {
+            Ok(burned.unwrap_or_default().checked_add(amount)?)
+        })?;
+    }
+
+    let kind = if from.eq(&config.module_address) {
+        TransferKind::Mint
+    } else if to.eq(&config.module_address) {
+        TransferKind::Burn
+    } else if is_excluded_from || is_excluded_to {
+        TransferKind::ExcludedFlow
+    } else {
+        TransferKind::Transfer
+    };
+
+    append_transfer(
+        storage,
+        Transfer {
+            id: 0,
+            denom: config.tracked_denom.clone(),
+            from,
+            to,
+            amount,
+            block_time: block_seconds,
+            block_height,
+            kind,
+        },
+    )?;
+
+    Ok(response)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{
+        mock_dependencies_with_balance, mock_env, mock_info, MockApi, MockQuerier, MockStorage
+    };
+    use cosmwasm_std::{coins, from_json, Coin};
+    use crate::msg::{AnomaliesResponse, DriftResponse, ExclusionEventsResponse, SupplyBreakdownResponse, EmissionStatusResponse, PauseStatusResponse, TrackFailuresResponse, QueryMsg, RemainingQuotaResponse, RolesResponse};
+    use crate::query::query;
+    use cosmwasm_std::OwnedDeps;
+    use cosmwasm_std::{Addr, Deps, Uint128};
+    use crate::state::{WalletCapStep, TOTAL_SUPPLY_HISTORY};
+
+    const USER: &str = "neutron1";
+    const ADMIN: &str = "neutron2";
+    const TOKEN_FACTORY: &str = "neutron4";
+    const NATIVE_DENOM: &str = "untrn";
+    const OTHER_DENOM: &str = "factory/neutron4/ucosmo";
+
+    fn proper_initialization() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies_with_balance(&coins(2, NATIVE_DENOM));
+
+        let msg = InstantiateMsg {
+            admin_addr: Addr::unchecked(ADMIN).to_string(),
+            tokenfactory_module_address: Addr::unchecked(TOKEN_FACTORY).to_string(),
+            tracked_denom: NATIVE_DENOM.to_string(),
+        };
+
+        let info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+
+        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        deps
+    }
+
+    fn denom_config() -> DenomConfig {
+        DenomConfig {
+            tracked_denom: NATIVE_DENOM.to_string(),
+            module_address: TOKEN_FACTORY.to_string(),
+            max_supply: None,
+            soulbound: false,
+            lenient: false,
+        }
+    }
+
+    fn send(from: &str, to: &str, amount: u128) -> SudoMsg {
+        SudoMsg::BlockBeforeSend {
+            from: from.to_string(),
+            to: to.to_string(),
+            amount: Coin::new(amount, NATIVE_DENOM),
+        }
+    }
+
+    #[test]
+    fn track_mint() {
+        let mut deps = proper_initialization();
+        let _info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+
+        let config = denom_config();
+        
+        let _res:Result<Response, ContractError> = track_balances(
+            &mut deps.storage,
+            mock_env().block.time.seconds(),
+            mock_env().block.height,
+            &config,
+            TOKEN_FACTORY.to_string(),
+            USER.to_string(),
+            Uint128::new(999),
+        );
+        
+        assert_eq!(TOTAL_SUPPLY_HISTORY.load(&mut deps.storage, NATIVE_DENOM).unwrap(), Uint128::new(999));
+    }
+
+    #[test]
+    fn track_burn() {
+        let mut deps = proper_initialization();
+        let _info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+
+        let config = denom_config();
+        
+        let _res:Result<Response, ContractError> = track_balances(
+            &mut deps.storage,
+            mock_env().block.time.seconds(),
+            mock_env().block.height,
+            &config,
+            TOKEN_FACTORY.to_string(),
+            USER.to_string(),
+            Uint128::new(100),
+        );
+        
+        let _res:Result<Response, ContractError> = track_balances(
+            &mut deps.storage,
+            mock_env().block.time.seconds(),
+            mock_env().block.height,
+            &config,
+            USER.to_string(),
+            TOKEN_FACTORY.to_string(),
+            Uint128::new(10),
+        );
+        
+        assert_eq!(TOTAL_SUPPLY_HISTORY.load(&mut deps.storage, NATIVE_DENOM).unwrap(), Uint128::new(90));
+    }
+
+    #[test]
+    fn track_balance() {
+        let mut deps = proper_initialization();
+        let _info = mock_info(ADMIN, &coins(1000, NATIVE_DENOM.to_string()));
+
+        let config = denom_config();
+        
+        let _res:Result<Response, ContractError> = track_balances(
+            &mut deps.storage,
+            mock_env().block.time.seconds(),
+            mock_env().block.height,
+            &config,
+            TOKEN_FACTORY.to_string(),
+            USER.to_string(),
+            Uint128::new(100),
+        );
+        
+        assert_eq!(BALANCES.may_load(&deps.storage, (NATIVE_DENOM, USER)).unwrap(), Some(Uint128::new(100)));
+    }
+
+    #[test]
+    fn track_multiple_denoms() {
+        let mut deps = proper_initialization();
+        let info = mock_info(ADMIN, &[]);
+
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: OTHER_DENOM.to_string(),
+            tokenfactory_module_address: TOKEN_FACTORY.to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 100));
+        assert!(res.is_ok());
+
+        let res = sudo(deps.as_mut(), mock_env(), SudoMsg::BlockBeforeSend {
+            from: TOKEN_FACTORY.to_string(),
+            to: USER.to_string(),
+            amount: Coin::new(40, OTHER_DENOM),
+        });
+        assert!(res.is_ok());
+
+        let res = sudo(deps.as_mut(), mock_env(), SudoMsg::BlockBeforeSend {
+            from: TOKEN_FACTORY.to_string(),
+            to: USER.to_string(),
+            amount: Coin::new(40, "uatom"),
+        });
+        assert_eq!(res.unwrap_err(), ContractError::DenomNotTracked { denom: "uatom".to_string() });
+
+        assert_eq!(BALANCES.may_load(&deps.storage, (NATIVE_DENOM, USER)).unwrap(), Some(Uint128::new(100)));
+        assert_eq!(BALANCES.may_load(&deps.storage, (OTHER_DENOM, USER)).unwrap(), Some(Uint128::new(40)));
+        assert_eq!(TOTAL_SUPPLY_HISTORY.load(&deps.storage, OTHER_DENOM).unwrap(), Uint128::new(40));
+    }
+
+    #[test]
+    fn track_burned_by() {
+        let mut deps = proper_initialization();
+
+        let config = denom_config();
+
+        for (from, to, amount) in [
+            (TOKEN_FACTORY, USER, 100),
+            (TOKEN_FACTORY, ADMIN, 100),
+            (USER, TOKEN_FACTORY, 10),
+            (USER, ADMIN, 20),
+            (ADMIN, TOKEN_FACTORY, 5),
+            (USER, TOKEN_FACTORY, 15),
+        ] {
+            track_balances(
+                &mut deps.storage,
+                mock_env().block.time.seconds(),
+                mock_env().block.height,
+                &config,
+                from.to_string(),
+                to.to_string(),
+                Uint128::new(amount),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(BURNED.load(&deps.storage, (NATIVE_DENOM, USER)).unwrap(), Uint128::new(25));
+        assert_eq!(BURNED.load(&deps.storage, (NATIVE_DENOM, ADMIN)).unwrap(), Uint128::new(5));
+        assert_eq!(TOTAL_BURNED_HISTORY.load(&deps.storage, NATIVE_DENOM).unwrap(), Uint128::new(30));
+        assert_eq!(TOTAL_SUPPLY_HISTORY.load(&deps.storage, NATIVE_DENOM).unwrap(), Uint128::new(170));
+
+        let top = crate::query::query_top_burners(deps.as_ref(), NATIVE_DENOM.to_string(), None, None).unwrap();
+        assert_eq!(
+            top.burners.iter().map(|b| (b.address.as_str(), b.burned.u128())).collect::<Vec<_>>(),
+            vec![(USER, 25), (ADMIN, 5)]
+        );
+
+        let top = crate::query::query_top_burners(deps.as_ref(), NATIVE_DENOM.to_string(), Some(1), Some(USER.to_string())).unwrap();
+        assert_eq!(top.burners.len(), 1);
+        assert_eq!(top.burners[0].address, ADMIN);
+    }
+
+    #[test]
+    fn track_transfer_journal() {
+        let mut deps = proper_initialization();
+        let info = mock_info(ADMIN, &[]);
+
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: "treasury".to_string(),
+            memo: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let config = denom_config();
+
+        for (from, to, amount) in [
+            (TOKEN_FACTORY, USER, 100),
+            (USER, ADMIN, 20),
+            (USER, "treasury", 30),
+            (ADMIN, TOKEN_FACTORY, 5),
+        ] {
+            track_balances(
+                &mut deps.storage,
+                mock_env().block.time.seconds(),
+                mock_env().block.height,
+                &config,
+                from.to_string(),
+                to.to_string(),
+                Uint128::new(amount),
+            )
+            .unwrap();
+        }
+
+        let res = crate::query::query_transfers(deps.as_ref(), None, None, None).unwrap();
+        assert_eq!(
+            res.transfers.iter().map(|t| (t.id, t.kind.clone())).collect::<Vec<_>>(),
+            vec![
+                (1, TransferKind::Mint),
+                (2, TransferKind::Transfer),
+                (3, TransferKind::ExcludedFlow),
+                (4, TransferKind::Burn),
+            ]
+        );
+
+        let res = crate::query::query_transfers(deps.as_ref(), Some(1), Some(10), Some(USER.to_string())).unwrap();
+        assert_eq!(res.transfers.iter().map(|t| t.id).collect::<Vec<_>>(), vec![2, 3]);
+
+        let res = crate::query::query_transfers(deps.as_ref(), None, Some(1), Some(ADMIN.to_string())).unwrap();
+        assert_eq!(res.transfers.len(), 1);
+        assert_eq!(res.transfers[0].amount, Uint128::new(20));
+    }
+
+    #[test]
+    fn track_average_balance() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+        let start = env.block.time.seconds();
+
+        let config = denom_config();
+
+        // USER holds 100 for 10 seconds, then 40 for 30 seconds
+        for (seconds, from, to, amount) in [
+            (0, TOKEN_FACTORY, USER, 100),
+            (10, USER, ADMIN, 60),
+            (40, ADMIN, TOKEN_FACTORY, 60),
+        ] {
+            track_balances(
+                &mut deps.storage,
+                start + seconds,
+                mock_env().block.height,
+                &config,
+                from.to_string(),
+                to.to_string(),
+                Uint128::new(amount),
+            )
+            .unwrap();
+        }
+
+        env.block.time = env.block.time.plus_seconds(40);
+
+        let msg = QueryMsg::AverageBalance {
+            denom: NATIVE_DENOM.to_string(),
+            address: USER.to_string(),
+            start,
+            end: start + 40,
+        };
+        let res: Uint128 = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res, Uint128::new(55));
+
+        let msg = QueryMsg::AverageBalance {
+            denom: NATIVE_DENOM.to_string(),
+            address: ADMIN.to_string(),
+            start: start + 5,
+            end: start + 25,
+        };
+        let res: Uint128 = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res, Uint128::new(45));
+
+        // Supply is 100 until the burn at 40
+        let msg = QueryMsg::AverageTotalSupply {
+            denom: NATIVE_DENOM.to_string(),
+            start,
+            end: start + 40,
+        };
+        let res: Uint128 = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res, Uint128::new(100));
+
+        let msg = QueryMsg::AverageTotalSupply {
+            denom: NATIVE_DENOM.to_string(),
+            start,
+            end: start + 41,
+        };
+        query(deps.as_ref(), env, msg).unwrap_err();
+    }
+
+    #[test]
+    fn migrate_checks_stored_version() {
+        let mut deps = proper_initialization();
+
+        set_contract_version(&mut deps.storage, "other_contract", "0.1.0").unwrap();
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::CannotMigrate {
+                contract: "other_contract".to_string(),
+                version: "0.1.0".to_string(),
+            }
+        );
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "9.0.0").unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, CONTRACT_VERSION).unwrap();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(get_contract_version(&deps.storage).unwrap().version, CONTRACT_VERSION);
+    }
+
+    #[test]
+    fn transfer_admin() {
+        let mut deps = proper_initialization();
+        let env = mock_env();
+
+        let msg = ExecuteMsg::ProposeNewAdmin {
+            new_admin: USER.to_string(),
+            expiry: Some(env.block.time.seconds() + 100),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let mut expired_env = env.clone();
+        expired_env.block.time = expired_env.block.time.plus_seconds(100);
+        let err = execute(deps.as_mut(), expired_env, mock_info(USER, &[]), ExecuteMsg::AcceptAdmin {}).unwrap_err();
+        assert_eq!(err, ContractError::AdminProposalExpired {});
+
+        execute(deps.as_mut(), env.clone(), mock_info(USER, &[]), ExecuteMsg::AcceptAdmin {}).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().admin_addr, Some(USER.to_string()));
+        assert_eq!(PENDING_ADMIN.may_load(&deps.storage).unwrap(), None);
+
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), ExecuteMsg::RenounceAdmin {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(USER, &[]), ExecuteMsg::RenounceAdmin {}).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().admin_addr, None);
+
+        let msg = ExecuteMsg::ProposeNewAdmin {
+            new_admin: ADMIN.to_string(),
+            expiry: None,
+        };
+        let err = execute(deps.as_mut(), env, mock_info(USER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn exclusion_manager_role() {
+        let mut deps = proper_initialization();
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: "treasury".to_string(),
+            memo: "treasury".to_string(),
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let grant = ExecuteMsg::GrantRole {
+            address: USER.to_string(),
+            role: Role::ExclusionManager,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), grant.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), grant).unwrap();
+
+        execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), msg).unwrap();
+
+        let res: RolesResponse = from_json(
+            query(deps.as_ref(), mock_env(), QueryMsg::Roles { address: USER.to_string() }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.roles, vec![Role::ExclusionManager]);
+
+        // Roles don't extend to admin-only messages
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: OTHER_DENOM.to_string(),
+            tokenfactory_module_address: TOKEN_FACTORY.to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+
+        let revoke = ExecuteMsg::RevokeRole {
+            address: USER.to_string(),
+            role: Role::ExclusionManager,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), revoke.clone()).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), revoke).unwrap_err();
+        assert_eq!(err, ContractError::RoleNotGranted {});
+
+        let msg = ExecuteMsg::IncludeWallet {
+            addr: "treasury".to_string(),
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+    }
+
+    #[test]
+    fn pause_blocks_sends() {
+        let mut deps = proper_initialization();
+
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), ExecuteMsg::Pause {}).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), ExecuteMsg::Pause {}).unwrap();
+
+        let msg = ExecuteMsg::SetPauseExemption {
+            address: TOKEN_FACTORY.to_string(),
+            exempt: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let err = sudo(deps.as_mut(), mock_env(), send(USER, ADMIN, 10)).unwrap_err();
+        assert_eq!(err, ContractError::Paused {});
+        sudo(deps.as_mut(), mock_env(), send(USER, TOKEN_FACTORY, 10)).unwrap();
+
+        let res: PauseStatusResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap()).unwrap();
+        assert!(res.paused);
+        assert_eq!(res.exempt_addresses, vec![TOKEN_FACTORY.to_string()]);
+
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), ExecuteMsg::Unpause {}).unwrap();
+        sudo(deps.as_mut(), mock_env(), send(USER, ADMIN, 10)).unwrap();
+    }
+
+    #[test]
+    fn blocked_address() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+
+        let msg = ExecuteMsg::BlockAddress {
+            address: USER.to_string(),
+            reason: "compromised".to_string(),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        let blocked_at = env.block.time.seconds();
+
+        let err = sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 10)).unwrap_err();
+        assert_eq!(err, ContractError::AddressBlocked { address: USER.to_string() });
+        let err = sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 10)).unwrap_err();
+        assert_eq!(err, ContractError::AddressBlocked { address: USER.to_string() });
+
+        // The token factory module can still burn from a frozen account
+        sudo(deps.as_mut(), env.clone(), send(USER, TOKEN_FACTORY, 10)).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = ExecuteMsg::UnblockAddress {
+            address: USER.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 10)).unwrap();
+
+        let is_blocked = |timestamp: u64| -> bool {
+            let msg = QueryMsg::IsBlocked {
+                address: USER.to_string(),
+                timestamp: Some(timestamp),
+            };
+            from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+        };
+        assert!(!is_blocked(blocked_at));
+        assert!(is_blocked(blocked_at + 1));
+        assert!(!is_blocked(blocked_at + 11));
+    }
+
+    #[test]
+    fn rate_limited_sends() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 1000)).unwrap();
+
+        let msg = ExecuteMsg::SetRateLimit {
+            address: None,
+            limit: Some(RateLimit {
+                max_per_tx: Some(Uint128::new(50)),
+                quota: Some(Decimal::percent(10)),
+                window: 86400,
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let err = sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 60)).unwrap_err();
+        assert_eq!(err, ContractError::TransferLimitExceeded { max: Uint128::new(50) });
+
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 50)).unwrap();
+        env.block.time = env.block.time.plus_seconds(3600);
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 40)).unwrap();
+
+        let remaining = |deps: Deps, env: &Env| -> RemainingQuotaResponse {
+            let msg = QueryMsg::RemainingQuota {
+                denom: NATIVE_DENOM.to_string(),
+                address: USER.to_string(),
+            };
+            from_json(query(deps, env.clone(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(remaining(deps.as_ref(), &env).remaining, Some(Uint128::new(10)));
+
+        let err = sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 20)).unwrap_err();
+        assert_eq!(err, ContractError::QuotaExceeded { remaining: Uint128::new(10) });
+
+        // Burns aren't limited
+        sudo(deps.as_mut(), env.clone(), send(USER, TOKEN_FACTORY, 20)).unwrap();
+
+        // The first send falls out of the window once its hour has passed
+        env.block.time = env.block.time.plus_seconds(86400);
+        assert_eq!(remaining(deps.as_ref(), &env).remaining, Some(Uint128::new(58)));
+
+        let msg = ExecuteMsg::SetRateLimitExemption {
+            address: USER.to_string(),
+            exempt: true,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 500)).unwrap();
+        assert_eq!(remaining(deps.as_ref(), &env).remaining, None);
+    }
+
+    #[test]
+    fn wallet_cap() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+        let now = env.block.time.seconds();
+
+        let msg = ExecuteMsg::SetWalletCap {
+            denom: NATIVE_DENOM.to_string(),
+            cap: Some(WalletCap {
+                max_balance: Uint128::new(100),
+                schedule: vec![
+                    WalletCapStep {
+                        starts_at: now + 100,
+                        max_balance: Some(Uint128::new(200)),
+                    },
+                    WalletCapStep {
+                        starts_at: now + 200,
+                        max_balance: None,
+                    },
+                ],
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 100)).unwrap();
+        let err = sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 1)).unwrap_err();
+        assert_eq!(err, ContractError::WalletCapExceeded { cap: Uint128::new(100) });
+
+        // Pools can receive past the cap
+        let msg = ExecuteMsg::SetWalletCapExemption {
+            address: ADMIN.to_string(),
+            exempt: true,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, ADMIN, 500)).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(100);
+        sudo(deps.as_mut(), env.clone(), send(ADMIN, USER, 100)).unwrap();
+        let err = sudo(deps.as_mut(), env.clone(), send(ADMIN, USER, 1)).unwrap_err();
+        assert_eq!(err, ContractError::WalletCapExceeded { cap: Uint128::new(200) });
+
+        env.block.time = env.block.time.plus_seconds(100);
+        sudo(deps.as_mut(), env.clone(), send(ADMIN, USER, 300)).unwrap();
+    }
+
+    #[test]
+    fn max_supply() {
+        let mut deps = proper_initialization();
+        let set_max_supply = |max_supply: u128| ExecuteMsg::SetMaxSupply {
+            denom: NATIVE_DENOM.to_string(),
+            max_supply: Uint128::new(max_supply),
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), set_max_supply(100)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), set_max_supply(100)).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), set_max_supply(101)).unwrap_err();
+        assert_eq!(err, ContractError::CannotRaiseMaxSupply {});
+
+        // The contract holds 2 and ADMIN 18 minted before registration, and the
+        // bank supply already includes each mint when the hook runs
+        deps.querier.update_balance(ADMIN, coins(18, NATIVE_DENOM));
+        deps.querier.update_balance(USER, coins(80, NATIVE_DENOM));
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 80)).unwrap();
+        deps.querier.update_balance(USER, coins(81, NATIVE_DENOM));
+        let err = sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 1)).unwrap_err();
+        assert_eq!(err, ContractError::MaxSupplyExceeded { max_supply: Uint128::new(100) });
+
+        // Burns free up room under the cap
+        deps.querier.update_balance(USER, coins(70, NATIVE_DENOM));
+        sudo(deps.as_mut(), mock_env(), send(USER, TOKEN_FACTORY, 10)).unwrap();
+        deps.querier.update_balance(USER, coins(80, NATIVE_DENOM));
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+    }
+
+    #[test]
+    fn emission_schedule() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+        let mint = |amount: u128| send(TOKEN_FACTORY, USER, amount);
+
+        let msg = ExecuteMsg::SetEmissionSchedule {
+            denom: NATIVE_DENOM.to_string(),
+            schedule: Some(EmissionSchedule {
+                start: env.block.time.seconds(),
+                epoch_length: 100,
+                initial_allowance: Uint128::new(1000),
+                decay: Decimal::percent(50),
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        sudo(deps.as_mut(), env.clone(), mint(600)).unwrap();
+        let err = sudo(deps.as_mut(), env.clone(), mint(500)).unwrap_err();
+        assert_eq!(err, ContractError::EmissionExceeded { epoch: 0, remaining: Uint128::new(400) });
+        sudo(deps.as_mut(), env.clone(), mint(400)).unwrap();
+
+        // The allowance halves in the next epoch
+        env.block.time = env.block.time.plus_seconds(150);
+        sudo(deps.as_mut(), env.clone(), mint(300)).unwrap();
+
+        let msg = QueryMsg::EmissionStatus {
+            denom: NATIVE_DENOM.to_string(),
+        };
+        let res: EmissionStatusResponse = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.epoch, Some(1));
+        assert_eq!(res.minted, Uint128::new(300));
+        assert_eq!(res.remaining, Some(Uint128::new(200)));
+
+        let err = sudo(deps.as_mut(), env.clone(), mint(201)).unwrap_err();
+        assert_eq!(err, ContractError::EmissionExceeded { epoch: 1, remaining: Uint128::new(200) });
+
+        // Over a billion epochs in, the allowance is still worked out in one go
+        let schedule = |decay: Decimal| ExecuteMsg::SetEmissionSchedule {
+            denom: NATIVE_DENOM.to_string(),
+            schedule: Some(EmissionSchedule {
+                start: 0,
+                epoch_length: 1,
+                initial_allowance: Uint128::new(1000),
+                decay,
+            }),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), schedule(Decimal::percent(50))).unwrap();
+        let err = sudo(deps.as_mut(), env.clone(), mint(1)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::EmissionExceeded {
+                epoch: env.block.time.seconds(),
+                remaining: Uint128::zero(),
+            }
+        );
+
+        // Without decay the allowance never runs out
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), schedule(Decimal::zero())).unwrap();
+        sudo(deps.as_mut(), env.clone(), mint(1000)).unwrap();
+    }
+
+    #[test]
+    fn soulbound_denom() {
+        let mut deps = proper_initialization();
+
+        let msg = ExecuteMsg::SetSoulbound {
+            denom: NATIVE_DENOM.to_string(),
+            soulbound: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+        sudo(deps.as_mut(), mock_env(), send(USER, TOKEN_FACTORY, 10)).unwrap();
+
+        let err = sudo(deps.as_mut(), mock_env(), send(USER, ADMIN, 10)).unwrap_err();
+        assert_eq!(err, ContractError::Soulbound { denom: NATIVE_DENOM.to_string() });
+
+        let msg = ExecuteMsg::SetSoulboundPair {
+            denom: NATIVE_DENOM.to_string(),
+            from: USER.to_string(),
+            to: ADMIN.to_string(),
+            allowed: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        sudo(deps.as_mut(), mock_env(), send(USER, ADMIN, 10)).unwrap();
+
+        // Pairs only allow the given direction
+        let err = sudo(deps.as_mut(), mock_env(), send(ADMIN, USER, 10)).unwrap_err();
+        assert_eq!(err, ContractError::Soulbound { denom: NATIVE_DENOM.to_string() });
+    }
+
+    #[test]
+    fn vesting_lock() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+        let now = env.block.time.seconds();
+
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 1100)).unwrap();
+
+        let msg = ExecuteMsg::CreateVesting {
+            denom: NATIVE_DENOM.to_string(),
+            address: USER.to_string(),
+            schedule: VestingSchedule {
+                total: Uint128::new(1000),
+                start: now,
+                cliff: now + 100,
+                end: now + 1000,
+            },
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 100)).unwrap();
+        let err = sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 1)).unwrap_err();
+        assert_eq!(err, ContractError::BalanceLocked { locked: Uint128::new(1000) });
+
+        // A tenth has vested past the cliff
+        env.block.time = env.block.time.plus_seconds(100);
+        let msg = QueryMsg::LockedBalance {
+            denom: NATIVE_DENOM.to_string(),
+            address: USER.to_string(),
+            timestamp: None,
+        };
+        let locked: Uint128 = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(locked, Uint128::new(900));
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 100)).unwrap();
+        let err = sudo(deps.as_mut(), env.clone(), send(USER, TOKEN_FACTORY, 1)).unwrap_err();
+        assert_eq!(err, ContractError::BalanceLocked { locked: Uint128::new(900) });
+
+        // Excluding the wallet doesn't unlock it
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: USER.to_string(),
+            memo: "team".to_string(),
+        };
+        deps.querier.update_balance(USER, coins(900, NATIVE_DENOM));
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        let err = sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 1)).unwrap_err();
+        assert_eq!(err, ContractError::BalanceLocked { locked: Uint128::new(900) });
+
+        let msg = ExecuteMsg::CancelVesting {
+            denom: NATIVE_DENOM.to_string(),
+            address: USER.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 900)).unwrap();
+    }
+
+    #[test]
+    fn track_hook_mode() {
+        let mut deps = proper_initialization();
+        let coin = |amount: u128| Coin::new(amount, NATIVE_DENOM);
+        let balance = |deps: Deps| -> Uint128 {
+            let msg = QueryMsg::BalanceAt {
+                denom: NATIVE_DENOM.to_string(),
+                address: USER.to_string(),
+                timestamp: None,
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+
+        let msg = ExecuteMsg::SetHookMode { mode: HookMode::Track };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        // Only the tracking hook does the accounting
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 100)).unwrap();
+        assert_eq!(balance(deps.as_ref()), Uint128::zero());
+        let mint = SudoMsg::TrackBeforeSend {
+            from: TOKEN_FACTORY.to_string(),
+            to: USER.to_string(),
+            amount: coin(100),
+        };
+        sudo(deps.as_mut(), mock_env(), mint).unwrap();
+        assert_eq!(balance(deps.as_ref()), Uint128::new(100));
+
+        // An underflow is recorded without touching the balances
+        let track = SudoMsg::TrackBeforeSend {
+            from: USER.to_string(),
+            to: ADMIN.to_string(),
+            amount: coin(150),
+        };
+        let res = sudo(deps.as_mut(), mock_env(), track).unwrap();
+        assert_eq!(res.attributes[0].value, "track_failed");
+        assert_eq!(balance(deps.as_ref()), Uint128::new(100));
+
+        let msg = QueryMsg::TrackFailures {
+            start_after: None,
+            limit: None,
+        };
+        let res: TrackFailuresResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.failures.len(), 1);
+        assert_eq!(res.failures[0].from, USER);
+        assert_eq!(res.failures[0].amount, Uint128::new(150));
+    }
+
+    #[test]
+    fn lenient_underflow() {
+        let mut deps = proper_initialization();
+
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 10)).unwrap();
+        sudo(deps.as_mut(), mock_env(), send(USER, ADMIN, 20)).unwrap_err();
+
+        let msg = ExecuteMsg::SetLenient {
+            denom: NATIVE_DENOM.to_string(),
+            lenient: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let res = sudo(deps.as_mut(), mock_env(), send(USER, ADMIN, 20)).unwrap();
+        assert_eq!(res.events[0].ty, "balance_anomaly");
+
+        let msg = QueryMsg::BalanceAt {
+            denom: NATIVE_DENOM.to_string(),
+            address: USER.to_string(),
+            timestamp: None,
+        };
+        let balance: Uint128 = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(balance, Uint128::zero());
+
+        let msg = QueryMsg::Anomalies {
+            start_after: None,
+            limit: None,
+        };
+        let res: AnomaliesResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.anomalies.len(), 1);
+        assert_eq!(res.anomalies[0].shortfall, Uint128::new(10));
+        assert_eq!(res.anomalies[0].tracked, Uint128::new(10));
+
+        // The receiver is credited in full, with the shortfall added to the supply
+        let msg = QueryMsg::BalanceAt {
+            denom: NATIVE_DENOM.to_string(),
+            address: ADMIN.to_string(),
+            timestamp: None,
+        };
+        let balance: Uint128 = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(balance, Uint128::new(20));
+
+        let msg = QueryMsg::TotalSupplyAt {
+            denom: NATIVE_DENOM.to_string(),
+            timestamp: None,
+        };
+        let total_supply: Uint128 = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(total_supply, Uint128::new(20));
+    }
+
+    #[test]
+    fn sync_with_bank() {
+        let mut deps = proper_initialization();
+        let create = ExecuteMsg::CreateCategory {
+            category: "team".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), create).unwrap();
+        let msg = ExecuteMsg::SetAddressCategory {
+            address: USER.to_string(),
+            category: Some("team".to_string()),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info(ADMIN, &[]), msg).unwrap();
+        sudo(deps.as_mut(), mock_env(), send(TOKEN_FACTORY, USER, 100)).unwrap();
+
+        // Tokens minted before the hook was registered
+        deps.querier.update_balance(USER, coins(150, NATIVE_DENOM));
+        deps.querier.update_balance(ADMIN, coins(20, NATIVE_DENOM));
+
+        let addresses = vec![USER.to_string(), ADMIN.to_string(), TOKEN_FACTORY.to_string()];
+        let msg = QueryMsg::Drift {
+            denom: NATIVE_DENOM.to_string(),
+            addresses: addresses.clone(),
+        };
+        let res: DriftResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(res.drifts.len(), 2);
+        assert_eq!(res.drifts[0].tracked, Uint128::new(100));
+        assert_eq!(res.drifts[0].bank, Uint128::new(150));
+
+        let msg = ExecuteMsg::Sync {
+            denom: NATIVE_DENOM.to_string(),
+            addresses,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info(USER, &[]), msg).unwrap();
+        assert_eq!(res.events.len(), 2);
+
+        let msg = QueryMsg::TotalSupplyAt {
+            denom: NATIVE_DENOM.to_string(),
+            timestamp: None,
+        };
+        let total_supply: Uint128 = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(total_supply, Uint128::new(170));
+
+        let msg = QueryMsg::SupplyBreakdownAt {
+            denom: NATIVE_DENOM.to_string(),
+            timestamp: None,
+        };
+        let breakdown: SupplyBreakdownResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(breakdown.total, Uint128::new(170));
+        assert_eq!(breakdown.circulating, Uint128::new(170));
+        assert_eq!(breakdown.excluded, Uint128::zero());
+
+        let msg = QueryMsg::CategorySupplyAt {
+            denom: NATIVE_DENOM.to_string(),
+            category: "team".to_string(),
+            timestamp: None,
+        };
+        let category_supply: Uint128 = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(category_supply, Uint128::new(150));
+    }
+
+    #[test]
+    fn import_balances() {
+        let mut deps = proper_initialization();
+        let env = mock_env();
+        let now = env.block.time.seconds();
+        deps.querier.update_balance(USER, coins(150, NATIVE_DENOM));
+
+        let import = |amount: u128| ExecuteMsg::ImportBalances {
+            denom: NATIVE_DENOM.to_string(),
+            balances: vec![ImportedBalance {
+                address: USER.to_string(),
+                amount: Uint128::new(amount),
+                checkpoints: vec![(now - 100, Uint128::new(50))],
+            }],
+        };
+
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER, &[]), import(150)).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), import(100)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ImportMismatch {
+                address: USER.to_string(),
+                bank: Uint128::new(150),
+            }
+        );
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), import(150)).unwrap();
+
+        let balance_at = |timestamp: u64| -> Uint128 {
+            let msg = QueryMsg::BalanceAt {
+                denom: NATIVE_DENOM.to_string(),
+                address: USER.to_string(),
+                timestamp: Some(timestamp),
+            };
+            from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(balance_at(now - 100), Uint128::zero());
+        assert_eq!(balance_at(now - 99), Uint128::new(50));
+        assert_eq!(balance_at(now), Uint128::new(150));
+
+        let total_supply_at = |timestamp: u64| -> Uint128 {
+            let msg = QueryMsg::TotalSupplyAt {
+                denom: NATIVE_DENOM.to_string(),
+                timestamp: Some(timestamp),
+            };
+            from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(total_supply_at(now - 100), Uint128::zero());
+        assert_eq!(total_supply_at(now - 99), Uint128::new(50));
+        assert_eq!(total_supply_at(now), Uint128::new(150));
+
+        let msg = QueryMsg::SupplyBreakdownAt {
+            denom: NATIVE_DENOM.to_string(),
+            timestamp: None,
+        };
+        let breakdown: SupplyBreakdownResponse = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(breakdown.total, Uint128::new(150));
+
+        // An address can only be imported once
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), import(150)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidImport {
+                address: USER.to_string(),
+                reason: "balance is already tracked".to_string(),
+            }
+        );
+
+        // A later batch can still reach back past supply changes since
+        let mut later = env.clone();
+        later.block.time = later.block.time.plus_seconds(10);
+        sudo(deps.as_mut(), later.clone(), send(TOKEN_FACTORY, USER, 5)).unwrap();
+        later.block.time = later.block.time.plus_seconds(10);
+        deps.querier.update_balance(ADMIN, coins(20, NATIVE_DENOM));
+        let msg = ExecuteMsg::ImportBalances {
+            denom: NATIVE_DENOM.to_string(),
+            balances: vec![ImportedBalance {
+                address: ADMIN.to_string(),
+                amount: Uint128::new(20),
+                checkpoints: vec![(now - 50, Uint128::new(10))],
+            }],
+        };
+        execute(deps.as_mut(), later.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let supply_at = |timestamp: Option<u64>| -> Uint128 {
+            let msg = QueryMsg::TotalSupplyAt {
+                denom: NATIVE_DENOM.to_string(),
+                timestamp,
+            };
+            from_json(query(deps.as_ref(), later.clone(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(supply_at(Some(now - 99)), Uint128::new(50));
+        assert_eq!(supply_at(Some(now - 49)), Uint128::new(60));
+        assert_eq!(supply_at(Some(now + 1)), Uint128::new(160));
+        assert_eq!(supply_at(Some(now + 11)), Uint128::new(165));
+        assert_eq!(supply_at(None), Uint128::new(175));
+
+        let average_supply = |start: u64, end: u64| -> Uint128 {
+            let msg = QueryMsg::AverageTotalSupply {
+                denom: NATIVE_DENOM.to_string(),
+                start,
+                end,
+            };
+            from_json(query(deps.as_ref(), later.clone(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(average_supply(now - 100, now - 50), Uint128::new(50));
+        assert_eq!(average_supply(now - 50, now), Uint128::new(60));
+
+        let msg = ExecuteMsg::SealImport {
+            denom: NATIVE_DENOM.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), import(150)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::ImportSealed {
+                denom: NATIVE_DENOM.to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn exclusion_moves_supply() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 100)).unwrap();
+        deps.querier.update_balance(USER, coins(100, NATIVE_DENOM));
+        let excluded_at = env.block.time.seconds();
+
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: USER.to_string(),
+            memo: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let total_supply_at = |deps: Deps, timestamp: u64| -> Uint128 {
+            let msg = QueryMsg::TotalSupplyAt {
+                denom: NATIVE_DENOM.to_string(),
+                timestamp: Some(timestamp),
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(total_supply_at(deps.as_ref(), excluded_at), Uint128::zero());
+        assert_eq!(total_supply_at(deps.as_ref(), excluded_at + 1), Uint128::zero());
+
+        // The wallet received more while excluded
+        env.block.time = env.block.time.plus_seconds(5);
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 30)).unwrap();
+        deps.querier.update_balance(USER, coins(130, NATIVE_DENOM));
+        env.block.time = env.block.time.plus_seconds(5);
+        let msg = ExecuteMsg::IncludeWallet {
+            addr: USER.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        assert_eq!(total_supply_at(deps.as_ref(), excluded_at + 10), Uint128::zero());
+        assert_eq!(total_supply_at(deps.as_ref(), excluded_at + 11), Uint128::new(130));
+
+        let msg = QueryMsg::BalanceAt {
+            denom: NATIVE_DENOM.to_string(),
+            address: USER.to_string(),
+            timestamp: None,
+        };
+        let balance: Uint128 = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(balance, Uint128::new(130));
+    }
+
+    #[test]
+    fn exclusion_history() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+        let before = env.block.time.seconds();
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let excluded_at = env.block.time.seconds();
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: USER.to_string(),
+            memo: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let included_at = env.block.time.seconds();
+        let msg = ExecuteMsg::IncludeWallet {
+            addr: USER.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let is_excluded_at = |deps: Deps, timestamp: u64| -> bool {
+            let msg = QueryMsg::IsExcludedAt {
+                address: USER.to_string(),
+                timestamp: Some(timestamp),
+            };
+            from_json(query(deps, env.clone(), msg).unwrap()).unwrap()
+        };
+        assert!(!is_excluded_at(deps.as_ref(), before));
+        assert!(!is_excluded_at(deps.as_ref(), excluded_at));
+        assert!(is_excluded_at(deps.as_ref(), excluded_at + 1));
+        assert!(!is_excluded_at(deps.as_ref(), included_at + 1));
+
+        let msg = QueryMsg::ExclusionEvents {
+            start_after: None,
+            limit: None,
+        };
+        let res: ExclusionEventsResponse = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.events.len(), 2);
+        assert_eq!(res.events[0].address, Addr::unchecked(USER));
+        assert!(res.events[0].excluded);
+        assert_eq!(res.events[0].memo, Some("treasury".to_string()));
+        assert_eq!(res.events[0].sender, Addr::unchecked(ADMIN));
+        assert_eq!(res.events[0].block_time, excluded_at);
+        assert!(!res.events[1].excluded);
+        assert_eq!(res.events[1].memo, None);
+        assert_eq!(res.events[1].block_time, included_at);
+    }
+
+    #[test]
+    fn supply_breakdown() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: ADMIN.to_string(),
+            memo: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 100)).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, ADMIN, 50)).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 30)).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(ADMIN, TOKEN_FACTORY, 20)).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(USER, TOKEN_FACTORY, 10)).unwrap();
+        let tracked_at = env.block.time.seconds();
+
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = QueryMsg::SupplyBreakdownAt {
+            denom: NATIVE_DENOM.to_string(),
+            timestamp: Some(tracked_at + 1),
+        };
+        let res: SupplyBreakdownResponse = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(
+            res,
+            SupplyBreakdownResponse {
+                total: Uint128::new(120),
+                circulating: Uint128::new(60),
+                excluded: Uint128::new(60),
+                minted: Uint128::new(150),
+                burned: Uint128::new(30),
+            }
+        );
+    }
+
+    #[test]
+    fn register_denom_with_excluded_holders() {
+        let mut deps = proper_initialization();
+        let env = mock_env();
+
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: USER.to_string(),
+            memo: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        let create = ExecuteMsg::CreateCategory {
+            category: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), create).unwrap();
+        let msg = ExecuteMsg::SetAddressCategory {
+            address: USER.to_string(),
+            category: Some("treasury".to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        // The treasury holds the denom from before it was registered
+        deps.querier.update_balance(USER, coins(100, OTHER_DENOM));
+        let msg = ExecuteMsg::RegisterDenom {
+            denom: OTHER_DENOM.to_string(),
+            tokenfactory_module_address: TOKEN_FACTORY.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let msg = SudoMsg::BlockBeforeSend {
+            from: USER.to_string(),
+            to: ADMIN.to_string(),
+            amount: Coin::new(30, OTHER_DENOM),
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        deps.querier.update_balance(USER, coins(70, OTHER_DENOM));
+
+        let msg = ExecuteMsg::IncludeWallet {
+            addr: USER.to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        let msg = QueryMsg::SupplyBreakdownAt {
+            denom: OTHER_DENOM.to_string(),
+            timestamp: None,
+        };
+        let res: SupplyBreakdownResponse = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(res.total, Uint128::new(100));
+        assert_eq!(res.circulating, Uint128::new(100));
+        assert_eq!(res.excluded, Uint128::zero());
+
+        let msg = QueryMsg::CategorySupplyAt {
+            denom: OTHER_DENOM.to_string(),
+            category: "treasury".to_string(),
+            timestamp: None,
+        };
+        let category_supply: Uint128 = from_json(query(deps.as_ref(), env.clone(), msg).unwrap()).unwrap();
+        assert_eq!(category_supply, Uint128::new(70));
+    }
+
+    #[test]
+    fn category_supply() {
+        let mut deps = proper_initialization();
+        let mut env = mock_env();
+
+        let msg = ExecuteMsg::SetAddressCategory {
+            address: ADMIN.to_string(),
+            category: Some("treasury".to_string()),
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::CategoryNotFound { category: "treasury".to_string() });
+
+        let create = ExecuteMsg::CreateCategory {
+            category: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), create).unwrap();
+
+        // Existing holdings are carried into the category
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, ADMIN, 40)).unwrap();
+        env.block.time = env.block.time.plus_seconds(10);
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+
+        sudo(deps.as_mut(), env.clone(), send(TOKEN_FACTORY, USER, 100)).unwrap();
+        sudo(deps.as_mut(), env.clone(), send(USER, ADMIN, 30)).unwrap();
+        let sent_at = env.block.time.seconds();
+
+        env.block.time = env.block.time.plus_seconds(10);
+        sudo(deps.as_mut(), env.clone(), send(ADMIN, USER, 20)).unwrap();
+
+        let category_supply_at = |deps: Deps, timestamp: u64| -> Uint128 {
+            let msg = QueryMsg::CategorySupplyAt {
+                denom: NATIVE_DENOM.to_string(),
+                category: "treasury".to_string(),
+                timestamp: Some(timestamp),
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(category_supply_at(deps.as_ref(), sent_at), Uint128::zero());
+        assert_eq!(category_supply_at(deps.as_ref(), sent_at + 1), Uint128::new(70));
+        assert_eq!(category_supply_at(deps.as_ref(), sent_at + 11), Uint128::new(50));
+
+        // Once excluded, the wallet's bank balance counts instead
+        deps.querier.update_balance(ADMIN, coins(60, NATIVE_DENOM));
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = ExecuteMsg::ExcludeWallet {
+            addr: ADMIN.to_string(),
+            memo: "treasury".to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(ADMIN, &[]), msg).unwrap();
+        assert_eq!(category_supply_at(deps.as_ref(), sent_at + 21), Uint128::new(60));
+    }
+}
@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -9,12 +9,84 @@ pub enum ContractError {
     #[error("Unauthorized")]
     Unauthorized {},
 
-    #[error("Invalid denom, expected {expected_denom}")]
-    InvalidDenom { expected_denom: String },
+    #[error("Denom {denom} is not tracked")]
+    DenomNotTracked { denom: String },
+
+    #[error("Denom {denom} is already tracked")]
+    DenomAlreadyTracked { denom: String },
 
     #[error("Token already whitelisted")]
     TokenAlreadyWhitelisted {},
 
     #[error("Token not found")]
     TokenNotFound {},
+
+    #[error("No pending admin proposal")]
+    NoPendingAdmin {},
+
+    #[error("Admin proposal expired")]
+    AdminProposalExpired {},
+
+    #[error("Transfers are paused")]
+    Paused {},
+
+    #[error("Address {address} is blocked")]
+    AddressBlocked { address: String },
+
+    #[error("Send exceeds the per-transaction max of {max}")]
+    TransferLimitExceeded { max: Uint128 },
+
+    #[error("Send exceeds the remaining quota of {remaining}")]
+    QuotaExceeded { remaining: Uint128 },
+
+    #[error("Rate limit window must be positive when a quota is set")]
+    InvalidRateLimit {},
+
+    #[error("Receiver balance would exceed the wallet cap of {cap}")]
+    WalletCapExceeded { cap: Uint128 },
+
+    #[error("Wallet cap schedule must be ordered by start time")]
+    InvalidWalletCap {},
+
+    #[error("Mint would exceed the max supply of {max_supply}")]
+    MaxSupplyExceeded { max_supply: Uint128 },
+
+    #[error("Max supply can only be lowered")]
+    CannotRaiseMaxSupply {},
+
+    #[error("Mint exceeds the remaining allowance of {remaining} for epoch {epoch}")]
+    EmissionExceeded { epoch: u64, remaining: Uint128 },
+
+    #[error("Emission schedule needs a positive epoch length and a decay of at most 1")]
+    InvalidEmissionSchedule {},
+
+    #[error("Denom {denom} is soulbound")]
+    Soulbound { denom: String },
+
+    #[error("Send would drop the balance below the locked amount of {locked}")]
+    BalanceLocked { locked: Uint128 },
+
+    #[error("Vesting schedule must satisfy start <= cliff <= end and start < end")]
+    InvalidVestingSchedule {},
+
+    #[error("Balance import for {denom} is sealed")]
+    ImportSealed { denom: String },
+
+    #[error("Imported balance of {address} doesn't match its bank balance of {bank}")]
+    ImportMismatch { address: String, bank: Uint128 },
+
+    #[error("Cannot import {address}: {reason}")]
+    InvalidImport { address: String, reason: String },
+
+    #[error("Category {category} not found")]
+    CategoryNotFound { category: String },
+
+    #[error("Category {category} already exists")]
+    CategoryAlreadyExists { category: String },
+
+    #[error("Role not granted")]
+    RoleNotGranted {},
+
+    #[error("Cannot migrate from {contract} {version}")]
+    CannotMigrate { contract: String, version: String },
 }
@@ -1,15 +1,515 @@
-use cosmwasm_std::{MessageInfo, DepsMut};
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, MessageInfo, Order, QuerierWrapper, Record, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw_storage_plus::SnapshotMap;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::iter::Peekable;
+use std::ops::Bound;
 use crate::error::ContractError;
-use crate::state::{CONFIG};
+use crate::state::{
+    Anomaly, DenomConfig, ExclusionEvent, Role, TrackFailure, Transfer, ADDRESS_CATEGORY,
+    ADDRESS_TRANSFERS, ANOMALIES, ANOMALY_SEQ, CATEGORY_SUPPLY,
+    BALANCES, BALANCE_TWAB, CONFIG, EXCLUDED_SUPPLY_HISTORY, EXCLUDED_WALLETS, EXCLUSION_EVENTS,
+    EXCLUSION_EVENT_SEQ, ROLES, TOTAL_SUPPLY_HISTORY, TOTAL_SUPPLY_TWAB, TRACKED_SUPPLY,
+    TRACK_FAILURES, TRACK_FAILURE_SEQ, TRANSFERS, TRANSFER_SEQ,
+};
 
 
 
 pub fn check_is_admin(deps: &DepsMut, info: MessageInfo) -> Result<(), ContractError> {
     let config = CONFIG.load(deps.storage)?;
     
-    if info.sender != config.admin_addr {
+    if config.admin_addr.as_deref() != Some(info.sender.as_str()) {
         return Err(ContractError::Unauthorized {});
     } else {
         return Ok(());
     }
 }
+
+/// Passes if the sender holds `role`, falling back to the admin check.
+pub fn check_has_role(deps: &DepsMut, info: &MessageInfo, role: Role) -> Result<(), ContractError> {
+    if ROLES.has(deps.storage, (&info.sender, role.as_str())) {
+        return Ok(());
+    }
+
+    check_is_admin(deps, info.clone())
+}
+
+/// Appends a transfer to the journal under the next sequence id.
+pub fn append_transfer(storage: &mut dyn Storage, mut transfer: Transfer) -> StdResult<u64> {
+    let id = TRANSFER_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    transfer.id = id;
+
+    TRANSFERS.save(storage, id, &transfer)?;
+    ADDRESS_TRANSFERS.save(storage, (&transfer.from, id), &())?;
+    if transfer.to != transfer.from {
+        ADDRESS_TRANSFERS.save(storage, (&transfer.to, id), &())?;
+    }
+    TRANSFER_SEQ.save(storage, &id)?;
+
+    Ok(id)
+}
+
+/// Balance of `denom` the bank module holds for `address`.
+pub fn bank_balance(querier: &QuerierWrapper, address: &str, denom: &str) -> StdResult<Uint128> {
+    Ok(querier.query_balance(address, denom)?.amount)
+}
+
+/// Balance `address` is accounted with: its tracked balance, or its bank
+/// balance if it's an excluded wallet.
+pub fn held_balance(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    denom: &str,
+    address: &str,
+) -> StdResult<Uint128> {
+    if EXCLUDED_WALLETS.may_load(storage, &Addr::unchecked(address))?.is_some() {
+        bank_balance(querier, address, denom)
+    } else {
+        Ok(BALANCES.may_load(storage, (denom, address))?.unwrap_or_default())
+    }
+}
+
+/// Returns the tracked and bank balances of `address` if they differ. Excluded
+/// wallets and the module address never drift.
+pub fn balance_drift(
+    deps: Deps,
+    config: &DenomConfig,
+    address: &Addr,
+) -> StdResult<Option<(Uint128, Uint128)>> {
+    if address.as_str() == config.module_address || EXCLUDED_WALLETS.may_load(deps.storage, address)?.is_some() {
+        return Ok(None);
+    }
+
+    let tracked = BALANCES
+        .may_load(deps.storage, (&config.tracked_denom, address.as_str()))?
+        .unwrap_or_default();
+    let bank = bank_balance(&deps.querier, address.as_str(), &config.tracked_denom)?;
+
+    Ok((tracked != bank).then_some((tracked, bank)))
+}
+
+/// Replaces the tracked balance of `address`, moving the circulating supply by
+/// the difference. Both are snapshotted at `block_seconds`.
+pub fn reset_balance(
+    storage: &mut dyn Storage,
+    block_seconds: u64,
+    denom: &str,
+    address: &str,
+    tracked: Uint128,
+    balance: Uint128,
+) -> StdResult<()> {
+    if balance.is_zero() {
+        BALANCES.remove(storage, (denom, address), block_seconds)?;
+    } else {
+        BALANCES.save(storage, (denom, address), &balance, block_seconds)?;
+    }
+    BALANCE_TWAB.update::<_, StdError>(storage, (denom, address), block_seconds, |twab| {
+        twab.unwrap_or_default().advance(block_seconds, balance)
+    })?;
+
+    if balance > tracked {
+        let drift = balance - tracked;
+        adjust_supply(storage, block_seconds, denom, SupplyBucket::Circulating, drift, true)?;
+    } else if tracked > balance {
+        let drift = tracked - balance;
+        adjust_supply(storage, block_seconds, denom, SupplyBucket::Circulating, drift, false)?;
+    }
+
+    Ok(())
+}
+
+/// Part of the tracked supply a holding counts toward.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SupplyBucket {
+    Circulating,
+    Excluded,
+}
+
+/// Moves `amount` into or out of the circulating or excluded supply of `denom`,
+/// moving the total supply with it so it stays the sum of the two. Every
+/// supply change but an import goes through here, and a change the series
+/// can't absorb is an error rather than being clamped.
+pub fn adjust_supply(
+    storage: &mut dyn Storage,
+    block_seconds: u64,
+    denom: &str,
+    bucket: SupplyBucket,
+    amount: Uint128,
+    into: bool,
+) -> StdResult<()> {
+    let adjust = |supply: Option<Uint128>| -> StdResult<Uint128> {
+        let supply = supply.unwrap_or_default();
+        Ok(if into {
+            supply.checked_add(amount)?
+        } else {
+            supply.checked_sub(amount)?
+        })
+    };
+
+    match bucket {
+        SupplyBucket::Circulating => {
+            let circulating = TOTAL_SUPPLY_HISTORY.update(storage, denom, block_seconds, adjust)?;
+            TOTAL_SUPPLY_TWAB.update::<_, StdError>(storage, denom, block_seconds, |twab| {
+                twab.unwrap_or_default().advance(block_seconds, circulating)
+            })?;
+        }
+        SupplyBucket::Excluded => {
+            EXCLUDED_SUPPLY_HISTORY.update(storage, denom, block_seconds, adjust)?;
+        }
+    }
+    TRACKED_SUPPLY.update(storage, denom, block_seconds, adjust)?;
+
+    Ok(())
+}
+
+/// Adds imported balances to the circulating and total supply of `denom`.
+/// `changes` are (timestamp, previous, balance) and may predate the latest
+/// supply change, so the history from the earliest of them on is rewritten
+/// as if the balances had been tracked all along.
+pub fn import_supply(
+    storage: &mut dyn Storage,
+    denom: &str,
+    mut changes: Vec<(u64, Uint128, Uint128)>,
+) -> StdResult<()> {
+    changes.sort_by_key(|(timestamp, _, _)| *timestamp);
+    let since = match changes.first() {
+        Some((since, _, _)) => *since,
+        None => return Ok(()),
+    };
+
+    let circulating = rewrite_history(storage, &TOTAL_SUPPLY_HISTORY, denom, since, &changes)?;
+    rewrite_history(storage, &TRACKED_SUPPLY, denom, since, &changes)?;
+
+    let mut twab = truncate_history(storage, &TOTAL_SUPPLY_TWAB, denom, since)?;
+    for (timestamp, supply) in circulating {
+        twab = twab.advance(timestamp, supply)?;
+        TOTAL_SUPPLY_TWAB.save(storage, denom, &twab, timestamp)?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites the history of `key` in `series` from `since` on with the sorted
+/// balance `changes` added, returning the rewritten (timestamp, value) points.
+fn rewrite_history<'a>(
+    storage: &mut dyn Storage,
+    series: &SnapshotMap<'a, &'a str, Uint128>,
+    key: &'a str,
+    since: u64,
+    changes: &[(u64, Uint128, Uint128)],
+) -> StdResult<Vec<(u64, Uint128)>> {
+    // The value after each write is read before the writes are dropped
+    let heights = series
+        .changelog()
+        .prefix(key)
+        .keys(storage, Some(cw_storage_plus::Bound::inclusive(since)), None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut points = heights
+        .into_iter()
+        .map(|height| {
+            let value = series.may_load_at_height(storage, key, height + 1)?;
+            Ok((height, value.unwrap_or_default()))
+        })
+        .collect::<StdResult<Vec<_>>>()?
+        .into_iter()
+        .peekable();
+    let mut changes = changes.iter().peekable();
+
+    let mut value = truncate_history(storage, series, key, since)?;
+    let (mut added, mut removed) = (Uint128::zero(), Uint128::zero());
+    let mut rewritten = vec![];
+    loop {
+        let timestamp = match (points.peek(), changes.peek()) {
+            (Some((height, _)), Some((timestamp, _, _))) => *height.min(timestamp),
+            (Some((height, _)), None) => *height,
+            (None, Some((timestamp, _, _))) => *timestamp,
+            (None, None) => break,
+        };
+        while let Some((_, point)) = points.next_if(|(height, _)| *height == timestamp) {
+            value = point;
+        }
+        while let Some((_, previous, balance)) = changes.next_if(|(at, _, _)| *at == timestamp) {
+            added = added.checked_add(*balance)?;
+            removed = removed.checked_add(*previous)?;
+        }
+
+        let supply = value.checked_add(added)?.checked_sub(removed)?;
+        series.save(storage, key, &supply, timestamp)?;
+        rewritten.push((timestamp, supply));
+    }
+
+    Ok(rewritten)
+}
+
+/// Drops the history of `key` in `series` from `since` on, returning the value
+/// it had just before.
+fn truncate_history<'a, T>(
+    storage: &mut dyn Storage,
+    series: &SnapshotMap<'a, &'a str, T>,
+    key: &'a str,
+    since: u64,
+) -> StdResult<T>
+where
+    T: Serialize + DeserializeOwned + Clone + Default,
+{
+    let before = series.may_load_at_height(storage, key, since)?.unwrap_or_default();
+    let heights = series
+        .changelog()
+        .prefix(key)
+        .keys(storage, Some(cw_storage_plus::Bound::inclusive(since)), None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for height in heights {
+        series.changelog().remove(storage, (key, height));
+    }
+
+    // Restoring the value logs the dropped one at `since`, which goes too
+    series.save(storage, key, &before, since)?;
+    series.changelog().remove(storage, (key, since));
+
+    Ok(before)
+}
+
+/// Counts what the excluded wallets hold of `denom` in the bank toward its
+/// excluded supply and their categories, for a denom whose sends weren't
+/// tracked until now.
+pub fn seed_excluded_supply(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    block_seconds: u64,
+    denom: &str,
+) -> StdResult<()> {
+    let wallets = EXCLUDED_WALLETS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for wallet in wallets {
+        let balance = bank_balance(querier, wallet.as_str(), denom)?;
+        if !balance.is_zero() {
+            adjust_supply(storage, block_seconds, denom, SupplyBucket::Excluded, balance, true)?;
+            adjust_category_supply(storage, block_seconds, denom, wallet.as_str(), balance, true)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Moves `amount` into or out of the supply of the category `address` belongs to, if any.
+pub fn adjust_category_supply(
+    storage: &mut dyn Storage,
+    block_seconds: u64,
+    denom: &str,
+    address: &str,
+    amount: Uint128,
+    into: bool,
+) -> StdResult<()> {
+    let category = match ADDRESS_CATEGORY.may_load(storage, &Addr::unchecked(address))? {
+        Some(category) => category,
+        None => return Ok(()),
+    };
+
+    CATEGORY_SUPPLY.update::<_, StdError>(storage, (denom, &category), block_seconds, |supply| {
+        let supply = supply.unwrap_or_default();
+        Ok(if into {
+            supply.checked_add(amount)?
+        } else {
+            supply.checked_sub(amount)?
+        })
+    })?;
+
+    Ok(())
+}
+
+/// Records a clamped balance underflow under the next sequence id.
+pub fn append_anomaly(storage: &mut dyn Storage, mut anomaly: Anomaly) -> StdResult<u64> {
+    let id = ANOMALY_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    anomaly.id = id;
+
+    ANOMALIES.save(storage, id, &anomaly)?;
+    ANOMALY_SEQ.save(storage, &id)?;
+
+    Ok(id)
+}
+
+/// Records a wallet exclusion or inclusion under the next sequence id.
+pub fn append_exclusion_event(storage: &mut dyn Storage, mut event: ExclusionEvent) -> StdResult<u64> {
+    let id = EXCLUSION_EVENT_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    event.id = id;
+
+    EXCLUSION_EVENTS.save(storage, id, &event)?;
+    EXCLUSION_EVENT_SEQ.save(storage, &id)?;
+
+    Ok(id)
+}
+
+/// Records a failed track-mode accounting under the next sequence id.
+pub fn append_track_failure(storage: &mut dyn Storage, mut failure: TrackFailure) -> StdResult<u64> {
+    let id = TRACK_FAILURE_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    failure.id = id;
+
+    TRACK_FAILURES.save(storage, id, &failure)?;
+    TRACK_FAILURE_SEQ.save(storage, &id)?;
+
+    Ok(id)
+}
+
+/// Buffers writes on top of a read-only store, so they can be applied all
+/// at once or dropped.
+pub struct StorageCache<'a> {
+    base: &'a dyn Storage,
+    writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+}
+
+impl<'a> StorageCache<'a> {
+    pub fn new(base: &'a dyn Storage) -> Self {
+        StorageCache {
+            base,
+            writes: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the buffered writes, `None` marking a removal.
+    pub fn into_writes(self) -> BTreeMap<Vec<u8>, Option<Vec<u8>>> {
+        self.writes
+    }
+}
+
+impl Storage for StorageCache<'_> {
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        match self.writes.get(key) {
+            Some(value) => value.clone(),
+            None => self.base.get(key),
+        }
+    }
+
+    fn range<'b>(
+        &'b self,
+        start: Option<&[u8]>,
+        end: Option<&[u8]>,
+        order: Order,
+    ) -> Box<dyn Iterator<Item = Record> + 'b> {
+        let bounds = (
+            start.map_or(Bound::Unbounded, |start| Bound::Included(start.to_vec())),
+            end.map_or(Bound::Unbounded, |end| Bound::Excluded(end.to_vec())),
+        );
+        let writes = self.writes.range::<Vec<u8>, _>(bounds);
+        let writes: Box<dyn Iterator<Item = BufferedWrite>> = match order {
+            Order::Ascending => Box::new(writes),
+            Order::Descending => Box::new(writes.rev()),
+        };
+
+        Box::new(MergedRange {
+            base: self.base.range(start, end, order).peekable(),
+            writes: writes.peekable(),
+            order,
+        })
+    }
+
+    fn set(&mut self, key: &[u8], value: &[u8]) {
+        self.writes.insert(key.to_vec(), Some(value.to_vec()));
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        self.writes.insert(key.to_vec(), None);
+    }
+}
+
+/// Buffered write of a `StorageCache`, `None` marking a removal.
+type BufferedWrite<'b> = (&'b Vec<u8>, &'b Option<Vec<u8>>);
+
+/// Walks a range of the base store and the buffered writes side by side,
+/// letting writes shadow the base records they replace or remove.
+struct MergedRange<'b> {
+    base: Peekable<Box<dyn Iterator<Item = Record> + 'b>>,
+    writes: Peekable<Box<dyn Iterator<Item = BufferedWrite<'b>> + 'b>>,
+    order: Order,
+}
+
+impl Iterator for MergedRange<'_> {
+    type Item = Record;
+
+    fn next(&mut self) -> Option<Record> {
+        loop {
+            let next = match (self.base.peek(), self.writes.peek()) {
+                (None, None) => return None,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((base_key, _)), Some((write_key, _))) => match self.order {
+                    Order::Ascending => base_key.cmp(write_key),
+                    Order::Descending => write_key.cmp(&base_key),
+                },
+            };
+
+            match next {
+                Ordering::Less => return self.base.next(),
+                Ordering::Equal => {
+                    self.base.next();
+                }
+                Ordering::Greater => {}
+            }
+            if let (key, Some(value)) = self.writes.next()? {
+                return Some((key.clone(), value.clone()));
+            }
+        }
+    }
+}
+
+/// Applies writes collected by a `StorageCache`.
+pub fn apply_writes(storage: &mut dyn Storage, writes: BTreeMap<Vec<u8>, Option<Vec<u8>>>) {
+    for (key, value) in writes {
+        match value {
+            Some(value) => storage.set(&key, &value),
+            None => storage.remove(&key),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn storage_cache_range() {
+        let mut base = MockStorage::new();
+        for key in [b"a", b"b", b"c", b"d"] {
+            base.set(key, b"base");
+        }
+
+        let mut cache = StorageCache::new(&base);
+        cache.set(b"b", b"cache");
+        cache.remove(b"c");
+        cache.set(b"e", b"cache");
+        cache.set(b"0", b"cache");
+
+        let range = |start: Option<&[u8]>, end: Option<&[u8]>, order: Order| {
+            cache
+                .range(start, end, order)
+                .map(|(key, value)| (String::from_utf8(key).unwrap(), String::from_utf8(value).unwrap()))
+                .collect::<Vec<_>>()
+        };
+        let records = |records: &[(&str, &str)]| {
+            records
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            range(None, None, Order::Ascending),
+            records(&[("0", "cache"), ("a", "base"), ("b", "cache"), ("d", "base"), ("e", "cache")])
+        );
+        assert_eq!(
+            range(Some(b"b"), Some(b"e"), Order::Descending),
+            records(&[("d", "base"), ("b", "cache")])
+        );
+        assert_eq!(
+            cache.range(Some(b"a"), None, Order::Descending).next(),
+            Some((b"e".to_vec(), b"cache".to_vec()))
+        );
+    }
+}
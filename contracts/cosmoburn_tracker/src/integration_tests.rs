@@ -69,14 +69,14 @@ mod tests {
 
     mod track {
         use super::*;
-        use crate::msg::{ExecuteMsg, QueryMsg, ExcludedWalletsResponse, ListHoldersResponse};
+        use crate::msg::{ExecuteMsg, QueryMsg, ConfigResponse, ExcludedWalletsResponse, ListHoldersResponse};
 
         #[test]
         fn exclude_wallet() {
             let (mut app, cw_template_contract) = proper_instantiate();
 
             let msg = ExecuteMsg::ExcludeWallet {
-                addr: "untrn".to_string(),
+                addr: USER.to_string(),
                 memo: "native1".to_string(),
             };
 
@@ -85,13 +85,13 @@ mod tests {
 
             let query_res: ExcludedWalletsResponse = app
             .wrap()
-            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::GetExcludedWallets {})
+            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::GetExcludedWallets { start_after: None, limit: None })
             .unwrap();
 
             assert_eq!(query_res.excludedwallets.len(), 1);
 
             let msg = ExecuteMsg::IncludeWallet {
-                addr: "untrn".to_string(),
+                addr: USER.to_string(),
             };
 
             let cosmos_msg = cw_template_contract.call(msg).unwrap();
@@ -99,7 +99,7 @@ mod tests {
 
             let query_res: ExcludedWalletsResponse = app
             .wrap()
-            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::GetExcludedWallets {})
+            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::GetExcludedWallets { start_after: None, limit: None })
             .unwrap();
 
             assert_eq!(query_res.excludedwallets.len(), 0);
@@ -111,26 +111,52 @@ mod tests {
 
             let query_res: Uint128 = app
             .wrap()
-            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::BalanceAt { address: ADMIN.to_string(), timestamp: Some(1u64) })
+            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::BalanceAt { denom: NATIVE_DENOM.to_string(), address: ADMIN.to_string(), timestamp: Some(1u64) })
             .unwrap();
 
             assert_eq!(query_res, Uint128::new(0));
 
             let query_res: Uint128 = app
             .wrap()
-            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::TotalSupplyAt { timestamp: Some(1u64) })
+            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::TotalSupplyAt { denom: NATIVE_DENOM.to_string(), timestamp: Some(1u64) })
             .unwrap();
 
             assert_eq!(query_res, Uint128::new(0));
 
             let query_res: ListHoldersResponse = app
             .wrap()
-            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::GetHolders { from: None, limit: Some(10)})
+            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::GetHolders { denom: NATIVE_DENOM.to_string(), from: None, limit: Some(10), timestamp: None })
             .unwrap();
 
             assert_eq!(query_res.holders.len(), 0);
             
         }
 
+        #[test]
+        fn register_denom() {
+            let (mut app, cw_template_contract) = proper_instantiate();
+
+            let msg = ExecuteMsg::RegisterDenom {
+                denom: "factory/neutron2/ucosmo".to_string(),
+                tokenfactory_module_address: TOKEN_FACTORY.to_string(),
+            };
+
+            let cosmos_msg = cw_template_contract.call(msg.clone()).unwrap();
+            app.execute(Addr::unchecked(USER), cosmos_msg).unwrap_err();
+
+            let cosmos_msg = cw_template_contract.call(msg.clone()).unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
+
+            let cosmos_msg = cw_template_contract.call(msg).unwrap();
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap_err();
+
+            let query_res: ConfigResponse = app
+            .wrap()
+            .query_wasm_smart(&cw_template_contract.addr(), &QueryMsg::Config {})
+            .unwrap();
+
+            assert_eq!(query_res.tracked_denoms.len(), 2);
+        }
+
     }
 }
@@ -5,4 +5,6 @@ pub mod state;
 pub mod msg;
 pub mod functions;
 pub mod helpers;
+pub mod migrations;
+pub mod policy;
 pub mod integration_tests;
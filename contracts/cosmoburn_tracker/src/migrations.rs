@@ -0,0 +1,331 @@
+use cosmwasm_std::{Addr, DepsMut, Env, Order, StdResult, Uint128};
+use cw_storage_plus::{Item, Map, SnapshotItem, SnapshotMap, Strategy};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+
+use crate::functions::{reset_balance, seed_excluded_supply};
+use crate::state::{
+    Config, DenomConfig, ExclusionInfo, HookMode, Twab, BALANCES, BALANCE_TWAB, CONFIG, DENOMS,
+    EXCLUDED_WALLETS, STATE, TOTAL_SUPPLY_HISTORY, TOTAL_SUPPLY_TWAB, TRACKED_SUPPLY,
+};
+
+type Migration = fn(DepsMut, &Env) -> StdResult<()>;
+
+/// State migrations in the order they must be applied, each tagged with the
+/// contract version that introduced it.
+const MIGRATIONS: &[(&str, Migration)] = &[
+    ("0.2.0", migrate_excluded_wallets),
+    ("0.2.0", migrate_balances),
+    ("0.2.0", migrate_config),
+    ("0.2.0", seed_supply),
+];
+
+/// Applies every migration introduced after `from`, returning the versions
+/// that were migrated through.
+pub fn run_migrations(mut deps: DepsMut, env: &Env, from: &Version) -> StdResult<Vec<String>> {
+    let mut applied: Vec<String> = vec![];
+
+    for (version, migration) in MIGRATIONS {
+        if Version::parse(version).is_ok_and(|version| &version > from) {
+            migration(deps.branch(), env)?;
+            if !applied.iter().any(|applied| applied == version) {
+                applied.push(version.to_string());
+            }
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Moves excluded wallets from the legacy `State` item into `EXCLUDED_WALLETS`.
+pub fn migrate_excluded_wallets(deps: DepsMut, _env: &Env) -> StdResult<()> {
+    if let Some(state) = STATE.may_load(deps.storage)? {
+        // The legacy item kept no history, so the wallets count as excluded
+        // since the beginning of time
+        for (addr, memo) in state.excluded_wallets {
+            EXCLUDED_WALLETS.save(deps.storage, &Addr::unchecked(addr), &ExclusionInfo { memo }, 0)?;
+        }
+        STATE.remove(deps.storage);
+    }
+
+    Ok(())
+}
+
+/// `Config` as stored by 0.1.0, which tracked a single denom.
+#[derive(Serialize, Deserialize)]
+struct ConfigV0_1 {
+    d: Option<String>,
+    m: Option<String>,
+    admin_addr: String,
+}
+
+const CONFIG_V0_1: Item<ConfigV0_1> = Item::new("c");
+
+/// Balances as stored by 0.1.0, keyed by address alone.
+const BALANCES_V0_1: SnapshotMap<&str, Uint128> =
+    SnapshotMap::new("b", "b_chpts", "b_chlg", Strategy::EveryBlock);
+
+/// Supply history as stored by 0.1.0, for its single denom.
+const TOTAL_SUPPLY_HISTORY_V0_1: SnapshotItem<Uint128> =
+    SnapshotItem::new("t", "t_chpts", "t_chlg", Strategy::EveryBlock);
+
+/// Re-keys the 0.1.0 balances and supply history by the denom they were
+/// tracked under. Changelog entries move along with the current values, so
+/// queries at past timestamps keep resolving. 0.1.0 kept no time-weighted
+/// balances, so those start accruing from the migration. Must run before
+/// `migrate_config`, which overwrites the legacy config.
+pub fn migrate_balances(deps: DepsMut, env: &Env) -> StdResult<()> {
+    let storage = deps.storage;
+    let block_seconds = env.block.time.seconds();
+    let denom = match CONFIG_V0_1.load(storage)?.d {
+        Some(denom) => denom,
+        None => return Ok(()),
+    };
+
+    // Snapshot maps don't expose their primary records, so these are moved
+    // through plain maps over the same namespaces
+    let legacy_balances: Map<&str, Uint128> = Map::new("b");
+    let balances: Map<(&str, &str), Uint128> = Map::new("b");
+    let legacy_supply: Item<Uint128> = Item::new("t");
+    let supply: Map<&str, Uint128> = Map::new("t");
+
+    // Everything is read up front, as old and new keys share a namespace
+    let old_balances = legacy_balances
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let old_balance_changes = BALANCES_V0_1
+        .changelog()
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let old_supply = legacy_supply.may_load(storage)?;
+    let old_supply_changes = TOTAL_SUPPLY_HISTORY_V0_1
+        .changelog()
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (address, balance) in old_balances {
+        legacy_balances.remove(storage, &address);
+        balances.save(storage, (&denom, &address), &balance)?;
+        BALANCE_TWAB.save(storage, (&denom, &address), &Twab::starting_at(block_seconds, balance), block_seconds)?;
+    }
+    for ((address, height), change) in old_balance_changes {
+        BALANCES_V0_1.changelog().remove(storage, (&address, height));
+        BALANCES.changelog().save(storage, ((&denom, &address), height), &change)?;
+    }
+
+    if let Some(total_supply) = old_supply {
+        legacy_supply.remove(storage);
+        supply.save(storage, &denom, &total_supply)?;
+        TOTAL_SUPPLY_TWAB.save(storage, &denom, &Twab::starting_at(block_seconds, total_supply), block_seconds)?;
+    }
+    for (height, change) in old_supply_changes {
+        TOTAL_SUPPLY_HISTORY_V0_1.changelog().remove(storage, height);
+        TOTAL_SUPPLY_HISTORY.changelog().save(storage, (&denom, height), &change)?;
+    }
+
+    Ok(())
+}
+
+/// Moves the single denom of the 0.1.0 `Config { d, m }` into `DENOMS` under
+/// readable field names.
+pub fn migrate_config(deps: DepsMut, _env: &Env) -> StdResult<()> {
+    let storage = deps.storage;
+    let legacy = CONFIG_V0_1.load(storage)?;
+
+    if let (Some(tracked_denom), Some(module_address)) = (legacy.d, legacy.m) {
+        DENOMS.save(
+            storage,
+            &tracked_denom,
+            &DenomConfig {
+                tracked_denom: tracked_denom.clone(),
+                module_address,
+                max_supply: None,
+                soulbound: false,
+                lenient: false,
+            },
+        )?;
+    }
+
+    CONFIG.save(
+        storage,
+        &Config {
+            admin_addr: Some(legacy.admin_addr),
+            hook_mode: HookMode::Block,
+        },
+    )
+}
+
+/// Seeds the total and excluded supply of every denom, which 0.1.0 didn't
+/// record, from the circulating supply and the bank balances of the excluded
+/// wallets. 0.1.0 left the balances of excluded wallets in place and counted
+/// in the circulating supply, so those are zeroed first. Must run after
+/// `migrate_balances` and `migrate_config`, which re-key the balances and
+/// register the legacy denom.
+pub fn seed_supply(deps: DepsMut, env: &Env) -> StdResult<()> {
+    let block_seconds = env.block.time.seconds();
+    let denoms = DENOMS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let wallets = EXCLUDED_WALLETS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for denom in denoms {
+        let circulating = TOTAL_SUPPLY_HISTORY.may_load(deps.storage, &denom)?.unwrap_or_default();
+        TRACKED_SUPPLY.save(deps.storage, &denom, &circulating, block_seconds)?;
+
+        for wallet in &wallets {
+            let key = (denom.as_str(), wallet.as_str());
+            if let Some(tracked) = BALANCES.may_load(deps.storage, key)? {
+                reset_balance(deps.storage, block_seconds, &denom, wallet.as_str(), tracked, Uint128::zero())?;
+            }
+        }
+        seed_excluded_supply(deps.storage, &deps.querier, block_seconds, &denom)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::msg::{QueryMsg, SupplyBreakdownResponse};
+    use crate::query::query;
+    use crate::state::State;
+    use cosmwasm_std::{coins, from_json, Deps, Uint256};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use std::collections::HashMap;
+
+    #[test]
+    fn migrate_legacy_excluded_wallets() {
+        let mut deps = mock_dependencies();
+
+        let state = State {
+            excluded_wallets: HashMap::from([
+                ("neutron5".to_string(), "treasury".to_string()),
+                ("neutron6".to_string(), "lp".to_string()),
+            ]),
+        };
+        STATE.save(&mut deps.storage, &state).unwrap();
+
+        migrate_excluded_wallets(deps.as_mut(), &mock_env()).unwrap();
+
+        assert_eq!(STATE.may_load(&deps.storage).unwrap(), None);
+        assert_eq!(
+            EXCLUDED_WALLETS.load(&deps.storage, &Addr::unchecked("neutron5")).unwrap(),
+            ExclusionInfo { memo: "treasury".to_string() }
+        );
+        assert!(EXCLUDED_WALLETS
+            .may_load(&deps.storage, &Addr::unchecked("neutron6"))
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn migrate_legacy_config() {
+        let mut deps = mock_dependencies();
+
+        let legacy = ConfigV0_1 {
+            d: Some("untrn".to_string()),
+            m: Some("neutron4".to_string()),
+            admin_addr: "neutron2".to_string(),
+        };
+        CONFIG_V0_1.save(&mut deps.storage, &legacy).unwrap();
+
+        BALANCES_V0_1.save(&mut deps.storage, "neutron5", &Uint128::new(100), 10).unwrap();
+        BALANCES_V0_1.save(&mut deps.storage, "neutron5", &Uint128::new(60), 20).unwrap();
+        BALANCES_V0_1.save(&mut deps.storage, "neutron6", &Uint128::new(40), 20).unwrap();
+        TOTAL_SUPPLY_HISTORY_V0_1.save(&mut deps.storage, &Uint128::new(100), 10).unwrap();
+        // 0.1.0 kept counting the balance of a wallet after excluding it
+        BALANCES_V0_1.save(&mut deps.storage, "neutron7", &Uint128::new(20), 15).unwrap();
+        TOTAL_SUPPLY_HISTORY_V0_1.save(&mut deps.storage, &Uint128::new(120), 15).unwrap();
+        let state = State {
+            excluded_wallets: HashMap::from([("neutron7".to_string(), "treasury".to_string())]),
+        };
+        STATE.save(&mut deps.storage, &state).unwrap();
+        deps.querier.update_balance("neutron7", coins(25, "untrn"));
+
+        let applied = run_migrations(deps.as_mut(), &mock_env(), &Version::new(0, 1, 0)).unwrap();
+        assert_eq!(applied, vec!["0.2.0".to_string()]);
+
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap(),
+            Config {
+                admin_addr: Some("neutron2".to_string()),
+                hook_mode: HookMode::Block,
+            }
+        );
+        assert_eq!(
+            DENOMS.load(&deps.storage, "untrn").unwrap(),
+            DenomConfig {
+                tracked_denom: "untrn".to_string(),
+                module_address: "neutron4".to_string(),
+                max_supply: None,
+                soulbound: false,
+                lenient: false,
+            }
+        );
+
+        let balance_at = |deps: Deps, address: &str, timestamp: Option<u64>| -> Uint128 {
+            let msg = QueryMsg::BalanceAt {
+                denom: "untrn".to_string(),
+                address: address.to_string(),
+                timestamp,
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(balance_at(deps.as_ref(), "neutron5", Some(5)), Uint128::zero());
+        assert_eq!(balance_at(deps.as_ref(), "neutron5", Some(15)), Uint128::new(100));
+        assert_eq!(balance_at(deps.as_ref(), "neutron5", None), Uint128::new(60));
+        assert_eq!(balance_at(deps.as_ref(), "neutron6", Some(15)), Uint128::zero());
+        assert_eq!(balance_at(deps.as_ref(), "neutron6", None), Uint128::new(40));
+        assert_eq!(balance_at(deps.as_ref(), "neutron7", None), Uint128::zero());
+
+        // Time-weighted balances accrue from the migration on
+        let now = mock_env().block.time.seconds();
+        assert_eq!(
+            BALANCE_TWAB.load(&deps.storage, ("untrn", "neutron5")).unwrap(),
+            Twab { balance: Uint128::new(60), cumulative: Uint256::zero(), last_update: now }
+        );
+        assert_eq!(
+            TOTAL_SUPPLY_TWAB.load(&deps.storage, "untrn").unwrap(),
+            Twab { balance: Uint128::new(100), cumulative: Uint256::zero(), last_update: now }
+        );
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(10);
+        let msg = QueryMsg::AverageBalance {
+            denom: "untrn".to_string(),
+            address: "neutron5".to_string(),
+            start: now,
+            end: now + 10,
+        };
+        let average: Uint128 = from_json(query(deps.as_ref(), env, msg).unwrap()).unwrap();
+        assert_eq!(average, Uint128::new(60));
+
+        let total_supply_at = |deps: Deps, timestamp: Option<u64>| -> Uint128 {
+            let msg = QueryMsg::TotalSupplyAt {
+                denom: "untrn".to_string(),
+                timestamp,
+            };
+            from_json(query(deps, mock_env(), msg).unwrap()).unwrap()
+        };
+        assert_eq!(total_supply_at(deps.as_ref(), Some(5)), Uint128::zero());
+        assert_eq!(total_supply_at(deps.as_ref(), None), Uint128::new(100));
+
+        let msg = QueryMsg::SupplyBreakdownAt {
+            denom: "untrn".to_string(),
+            timestamp: None,
+        };
+        let breakdown: SupplyBreakdownResponse = from_json(query(deps.as_ref(), mock_env(), msg).unwrap()).unwrap();
+        assert_eq!(breakdown.circulating, Uint128::new(100));
+        assert_eq!(breakdown.excluded, Uint128::new(25));
+        assert_eq!(breakdown.total, Uint128::new(125));
+
+        // No legacy records are left behind
+        assert_eq!(BALANCES_V0_1.may_load(&deps.storage, "neutron5").unwrap(), None);
+        assert_eq!(TOTAL_SUPPLY_HISTORY_V0_1.may_load(&deps.storage).unwrap(), None);
+
+        let applied = run_migrations(deps.as_mut(), &mock_env(), &Version::new(0, 2, 0)).unwrap();
+        assert!(applied.is_empty());
+    }
+}
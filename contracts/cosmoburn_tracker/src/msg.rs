@@ -1,11 +1,24 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use cosmwasm_schema::QueryResponses;
-use cosmwasm_std::QueryResponse;
 use cosmwasm_std::Uint128;
 use cosmwasm_schema::cw_serde;
-use std::collections::HashMap;
 
+use crate::state::{
+    Anomaly, EmissionSchedule, ExclusionEvent, HookMode, PendingAdmin, RateLimit, Role, TrackFailure, Transfer,
+    VestingSchedule, WalletCap,
+};
+
+
+/// Balance seeded by `ImportBalances`.
+#[cw_serde]
+pub struct ImportedBalance {
+    pub address: String,
+    /// Current balance, checked against the bank module
+    pub amount: Uint128,
+    /// Earlier balances as (block time in seconds, amount), in ascending order
+    pub checkpoints: Vec<(u64, Uint128)>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
@@ -14,6 +27,9 @@ pub struct InstantiateMsg {
     pub admin_addr: String,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
@@ -25,6 +41,190 @@ pub enum ExecuteMsg {
     IncludeWallet {
         addr: String,
     },
+
+    RegisterDenom {
+        denom: String,
+        tokenfactory_module_address: String,
+    },
+
+    ProposeNewAdmin {
+        new_admin: String,
+        expiry: Option<u64>,
+    },
+
+    AcceptAdmin {},
+
+    RenounceAdmin {},
+
+    GrantRole {
+        address: String,
+        role: Role,
+    },
+
+    RevokeRole {
+        address: String,
+        role: Role,
+    },
+
+    Pause {},
+
+    Unpause {},
+
+    SetPauseExemption {
+        address: String,
+        exempt: bool,
+    },
+
+    BlockAddress {
+        address: String,
+        reason: String,
+    },
+
+    UnblockAddress {
+        address: String,
+    },
+
+    /// Sets the rate limit of an address, or the global one if no address is given.
+    /// Passing no limit removes it.
+    SetRateLimit {
+        address: Option<String>,
+        limit: Option<RateLimit>,
+    },
+
+    SetRateLimitExemption {
+        address: String,
+        exempt: bool,
+    },
+
+    /// Caps the minted supply of a denom. Once set, the cap can only be lowered.
+    SetMaxSupply {
+        denom: String,
+        max_supply: Uint128,
+    },
+
+    /// Locks part of an address' balance under a vesting schedule, replacing any existing one.
+    CreateVesting {
+        denom: String,
+        address: String,
+        schedule: VestingSchedule,
+    },
+
+    CancelVesting {
+        denom: String,
+        address: String,
+    },
+
+    /// Chooses which before-send hook runs the accounting.
+    SetHookMode {
+        mode: HookMode,
+    },
+
+    /// Seeds balances of holders from before the hook was registered. Can be
+    /// sent in batches until the import is sealed.
+    ImportBalances {
+        denom: String,
+        balances: Vec<ImportedBalance>,
+    },
+
+    /// Closes the balance import of a denom for good.
+    SealImport {
+        denom: String,
+    },
+
+    /// Adds an address category, such as treasury or team.
+    CreateCategory {
+        category: String,
+    },
+
+    /// Puts an address in a category, or takes it out if none is given.
+    SetAddressCategory {
+        address: String,
+        category: Option<String>,
+    },
+
+    /// Resets the tracked balances of the given addresses to their bank balances.
+    /// Anyone can call it.
+    Sync {
+        denom: String,
+        addresses: Vec<String>,
+    },
+
+    /// Clamps sender underflows of a denom to zero and records them instead of failing the send.
+    SetLenient {
+        denom: String,
+        lenient: bool,
+    },
+
+    /// Restricts a denom to mints and burns.
+    SetSoulbound {
+        denom: String,
+        soulbound: bool,
+    },
+
+    /// Allows or disallows transfers of a soulbound denom from one address to another.
+    SetSoulboundPair {
+        denom: String,
+        from: String,
+        to: String,
+        allowed: bool,
+    },
+
+    /// Sets the emission schedule of a denom. Passing no schedule removes it.
+    SetEmissionSchedule {
+        denom: String,
+        schedule: Option<EmissionSchedule>,
+    },
+
+    /// Sets the wallet cap of a denom. Passing no cap removes it.
+    SetWalletCap {
+        denom: String,
+        cap: Option<WalletCap>,
+    },
+
+    SetWalletCapExemption {
+        address: String,
+        exempt: bool,
+    },
+}
+
+impl ExecuteMsg {
+    /// Role that, besides the admin, may execute this message. `None` leaves
+    /// authorization to the handler.
+    pub fn required_role(&self) -> Option<Role> {
+        match self {
+            ExecuteMsg::ExcludeWallet { .. } | ExecuteMsg::IncludeWallet { .. } => {
+                Some(Role::ExclusionManager)
+            }
+            ExecuteMsg::Pause {}
+            | ExecuteMsg::Unpause {}
+            | ExecuteMsg::SetPauseExemption { .. } => Some(Role::Pauser),
+            ExecuteMsg::BlockAddress { .. }
+            | ExecuteMsg::UnblockAddress { .. }
+            | ExecuteMsg::SetRateLimit { .. }
+            | ExecuteMsg::SetRateLimitExemption { .. }
+            | ExecuteMsg::SetWalletCap { .. }
+            | ExecuteMsg::SetWalletCapExemption { .. } => Some(Role::PolicyManager),
+            ExecuteMsg::Sync { .. }
+            | ExecuteMsg::ImportBalances { .. }
+            | ExecuteMsg::SealImport { .. }
+            | ExecuteMsg::CreateCategory { .. }
+            | ExecuteMsg::SetAddressCategory { .. }
+            | ExecuteMsg::RegisterDenom { .. }
+            | ExecuteMsg::SetMaxSupply { .. }
+            | ExecuteMsg::SetEmissionSchedule { .. }
+            | ExecuteMsg::SetHookMode { .. }
+            | ExecuteMsg::SetLenient { .. }
+            | ExecuteMsg::CreateVesting { .. }
+            | ExecuteMsg::CancelVesting { .. }
+            | ExecuteMsg::SetSoulbound { .. }
+            | ExecuteMsg::SetSoulboundPair { .. }
+            | ExecuteMsg::ProposeNewAdmin { .. }
+            | ExecuteMsg::AcceptAdmin {}
+            | ExecuteMsg::RenounceAdmin {}
+            | ExecuteMsg::GrantRole { .. }
+            | ExecuteMsg::RevokeRole { .. } => None,
+        }
+    }
 }
 
 
@@ -40,40 +240,343 @@ pub struct ListHoldersResponse {
     pub holders: Vec<HolderBalanceResponse>,
 }
 
+#[cw_serde]
+pub struct BurnerResponse {
+    pub address: String,
+    pub burned: Uint128,
+}
+
+#[cw_serde]
+pub struct TopBurnersResponse {
+    pub burners: Vec<BurnerResponse>,
+}
+
+#[cw_serde]
+pub struct PauseStatusResponse {
+    pub paused: bool,
+    pub exempt_addresses: Vec<String>,
+}
+
+#[cw_serde]
+pub struct BlockedAddressResponse {
+    pub address: String,
+    pub reason: String,
+}
+
+#[cw_serde]
+pub struct BlockedAddressesResponse {
+    pub blocked: Vec<BlockedAddressResponse>,
+}
+
+#[cw_serde]
+pub struct RemainingQuotaResponse {
+    /// Rate limit applying to the address, if any
+    pub limit: Option<RateLimit>,
+    /// Amount the address can still send in the current window, if it has a quota
+    pub remaining: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct WalletCapResponse {
+    pub cap: Option<WalletCap>,
+    /// Cap in effect at the current block time, if any
+    pub max_balance: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct EmissionStatusResponse {
+    pub schedule: Option<EmissionSchedule>,
+    /// Current epoch, if the schedule has started
+    pub epoch: Option<u64>,
+    /// Amount minted during the current epoch
+    pub minted: Uint128,
+    /// Amount that can still be minted during the current epoch, if limited
+    pub remaining: Option<Uint128>,
+}
+
+#[cw_serde]
+pub struct SoulboundPairResponse {
+    pub from: String,
+    pub to: String,
+}
+
+#[cw_serde]
+pub struct SoulboundPairsResponse {
+    pub pairs: Vec<SoulboundPairResponse>,
+}
+
+#[cw_serde]
+pub struct BalanceDriftResponse {
+    pub address: String,
+    pub tracked: Uint128,
+    pub bank: Uint128,
+}
+
+#[cw_serde]
+pub struct DriftResponse {
+    /// Addresses whose tracked balance differs from their bank balance
+    pub drifts: Vec<BalanceDriftResponse>,
+}
+
+#[cw_serde]
+pub struct CategoriesResponse {
+    pub categories: Vec<String>,
+}
+
+#[cw_serde]
+pub struct CategoryMembersResponse {
+    pub addresses: Vec<String>,
+}
+
+#[cw_serde]
+pub struct SupplyBreakdownResponse {
+    /// Circulating plus excluded supply. Also counts balances found without a
+    /// mint, such as by sync or import, so it can exceed minted minus burned.
+    pub total: Uint128,
+    /// Total less what excluded wallets hold
+    pub circulating: Uint128,
+    /// Held by excluded wallets
+    pub excluded: Uint128,
+    /// Cumulative amount minted
+    pub minted: Uint128,
+    /// Cumulative amount burned
+    pub burned: Uint128,
+}
+
+#[cw_serde]
+pub struct ExclusionEventsResponse {
+    pub events: Vec<ExclusionEvent>,
+}
+
+#[cw_serde]
+pub struct AnomaliesResponse {
+    pub anomalies: Vec<Anomaly>,
+}
+
+#[cw_serde]
+pub struct TrackFailuresResponse {
+    pub failures: Vec<TrackFailure>,
+}
+
+#[cw_serde]
+pub struct RolesResponse {
+    pub roles: Vec<Role>,
+}
+
+#[cw_serde]
+pub struct TransfersResponse {
+    pub transfers: Vec<Transfer>,
+}
+
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     /// Return the balance of the given address at the given timestamp.
     #[returns(Uint128)]
     BalanceAt {
+        denom: String,
         address: String,
         timestamp: Option<u64>,
     },
     /// Return the total supply at the given timestamp.
     #[returns(Uint128)]
-    TotalSupplyAt { timestamp: Option<u64> },
+    TotalSupplyAt {
+        denom: String,
+        timestamp: Option<u64>,
+    },
     #[returns(ConfigResponse)]
     Config {},
     #[returns(ListHoldersResponse)]
     GetHolders {
+        denom: String,
         from: Option<String>,
         limit: Option<u32>,
         timestamp: Option<u64>,
     },
-    #[returns(QueryResponse)]
-    GetExcludedWallets {},
+    #[returns(ExcludedWalletsResponse)]
+    GetExcludedWallets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Return whether the given address was excluded at the given timestamp.
+    #[returns(bool)]
+    IsExcludedAt {
+        address: String,
+        timestamp: Option<u64>,
+    },
+    /// Return the history of wallet exclusions and inclusions.
+    #[returns(ExclusionEventsResponse)]
+    ExclusionEvents {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Return the cumulative amount burned by the given address at the given timestamp.
+    #[returns(Uint128)]
+    BurnedBy {
+        denom: String,
+        address: String,
+        timestamp: Option<u64>,
+    },
+    /// Return the cumulative amount burned at the given timestamp.
+    #[returns(Uint128)]
+    TotalBurnedAt {
+        denom: String,
+        timestamp: Option<u64>,
+    },
+    /// Return addresses ranked by cumulative amount burned, largest first.
+    #[returns(TopBurnersResponse)]
+    TopBurners {
+        denom: String,
+        limit: Option<u32>,
+        start_after: Option<String>,
+    },
+    /// Return the time-weighted average balance of the given address between
+    /// the `start` and `end` timestamps.
+    #[returns(Uint128)]
+    AverageBalance {
+        denom: String,
+        address: String,
+        start: u64,
+        end: u64,
+    },
+    /// Return the time-weighted average total supply between the `start` and
+    /// `end` timestamps.
+    #[returns(Uint128)]
+    AverageTotalSupply {
+        denom: String,
+        start: u64,
+        end: u64,
+    },
+    /// Return whether transfers are paused and which addresses are exempt.
+    #[returns(PauseStatusResponse)]
+    PauseStatus {},
+    /// Return whether the given address was blocked at the given timestamp.
+    #[returns(bool)]
+    IsBlocked {
+        address: String,
+        timestamp: Option<u64>,
+    },
+    /// Return the currently blocked addresses.
+    #[returns(BlockedAddressesResponse)]
+    BlockedAddresses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Return the rate limit of the given address and how much it can still send.
+    #[returns(RemainingQuotaResponse)]
+    RemainingQuota {
+        denom: String,
+        address: String,
+    },
+    /// Return the address categories.
+    #[returns(CategoriesResponse)]
+    Categories {},
+    /// Return the addresses in the given category.
+    #[returns(CategoryMembersResponse)]
+    CategoryMembers {
+        category: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Return the balance held by the given category at the given timestamp.
+    #[returns(Uint128)]
+    CategorySupplyAt {
+        denom: String,
+        category: String,
+        timestamp: Option<u64>,
+    },
+    /// Return the total, circulating and excluded supply at the given timestamp,
+    /// along with the amounts minted and burned so far.
+    #[returns(SupplyBreakdownResponse)]
+    SupplyBreakdownAt {
+        denom: String,
+        timestamp: Option<u64>,
+    },
+    /// Return the given addresses whose tracked balance differs from their bank balance.
+    #[returns(DriftResponse)]
+    Drift {
+        denom: String,
+        addresses: Vec<String>,
+    },
+    /// Return the balance underflows clamped in lenient mode.
+    #[returns(AnomaliesResponse)]
+    Anomalies {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Return the sends whose accounting failed in track mode.
+    #[returns(TrackFailuresResponse)]
+    TrackFailures {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Return the amount of the given address' balance locked at the given timestamp.
+    #[returns(Uint128)]
+    LockedBalance {
+        denom: String,
+        address: String,
+        timestamp: Option<u64>,
+    },
+    /// Return the pairs allowed to transfer the given soulbound denom.
+    #[returns(SoulboundPairsResponse)]
+    SoulboundPairs { denom: String },
+    /// Return how much of the given denom was minted this epoch and how much is left.
+    #[returns(EmissionStatusResponse)]
+    EmissionStatus { denom: String },
+    /// Return the wallet cap of the given denom.
+    #[returns(WalletCapResponse)]
+    WalletCap { denom: String },
+    /// Return the roles granted to the given address.
+    #[returns(RolesResponse)]
+    Roles { address: String },
+    /// Return journaled transfers in sequence order, optionally only those
+    /// sent or received by the given address.
+    #[returns(TransfersResponse)]
+    Transfers {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        address: Option<String>,
+    },
     
 }
 
 #[cw_serde]
 pub struct ConfigResponse {
+    /// Current admin
+    pub admin_addr: Option<String>,
+    /// Admin proposed by the current admin, awaiting acceptance
+    pub pending_admin: Option<PendingAdmin>,
+    /// Registered denoms
+    pub tracked_denoms: Vec<TrackedDenomResponse>,
+    /// Hook running the accounting
+    pub hook_mode: HookMode,
+}
+
+#[cw_serde]
+pub struct TrackedDenomResponse {
     /// Tracked denom
     pub tracked_denom: String,
     /// Token factory module address
     pub token_factory_module: String,
+    /// Cap on the minted supply
+    pub max_supply: Option<Uint128>,
+    /// Whether the denom can only be minted and burned
+    pub soulbound: bool,
+    /// Whether sender underflows are clamped instead of failing the send
+    pub lenient: bool,
+    /// Whether the balance import is sealed
+    pub import_sealed: bool,
+    /// Circulating plus excluded supply, as in `SupplyBreakdownAt`
+    pub total_supply: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExcludedWalletResponse {
+    pub address: String,
+    pub memo: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct ExcludedWalletsResponse {
-    pub excludedwallets: HashMap<String, String>,
+    pub excludedwallets: Vec<ExcludedWalletResponse>,
 }
\ No newline at end of file
@@ -0,0 +1,301 @@
+use cosmwasm_std::{Addr, Order, QuerierWrapper, StdError, StdResult, Storage, Uint128};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::functions::held_balance;
+use crate::state::{
+    DenomConfig, RateLimit, ADDRESS_RATE_LIMITS, BALANCES, BLOCKED, EMISSION_SCHEDULES,
+    EPOCH_MINTED, EXCLUDED_WALLETS, PAUSED, PAUSE_EXEMPT, QUOTA_USAGE, RATE_LIMIT,
+    RATE_LIMIT_EXEMPT, SOULBOUND_PAIRS, TOTAL_SUPPLY_HISTORY, VESTING, WALLET_CAPS, WALLET_CAP_EXEMPT,
+};
+
+/// Rejects the send while transfers are paused, unless either side is exempt.
+pub fn check_not_paused(storage: &dyn Storage, from: &str, to: &str) -> Result<(), ContractError> {
+    if !PAUSED.may_load(storage)?.unwrap_or_default() {
+        return Ok(());
+    }
+
+    if PAUSE_EXEMPT.has(storage, &Addr::unchecked(from))
+        || PAUSE_EXEMPT.has(storage, &Addr::unchecked(to))
+    {
+        return Ok(());
+    }
+
+    Err(ContractError::Paused {})
+}
+
+/// Rejects the send if either side is blocked. Burns from a blocked address
+/// go through so the token factory module can still remove its funds.
+pub fn check_not_blocked(
+    storage: &dyn Storage,
+    config: &DenomConfig,
+    from: &str,
+    to: &str,
+) -> Result<(), ContractError> {
+    if to == config.module_address {
+        return Ok(());
+    }
+
+    for address in [from, to] {
+        if BLOCKED
+            .may_load(storage, &Addr::unchecked(address))?
+            .is_some()
+        {
+            return Err(ContractError::AddressBlocked {
+                address: address.to_string(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Rate limit applying to the address, if it isn't exempt.
+pub fn rate_limit_for(storage: &dyn Storage, address: &Addr) -> StdResult<Option<RateLimit>> {
+    if RATE_LIMIT_EXEMPT.has(storage, address) {
+        return Ok(None);
+    }
+
+    match ADDRESS_RATE_LIMITS.may_load(storage, address)? {
+        Some(limit) => Ok(Some(limit)),
+        None => RATE_LIMIT.may_load(storage),
+    }
+}
+
+/// Length of the buckets quota usage is summed into.
+const QUOTA_BUCKET_SECONDS: u64 = 3600;
+
+/// First bucket that still overlaps the window ending at `block_seconds`.
+fn first_bucket_in_window(block_seconds: u64, window: u64) -> u64 {
+    block_seconds.saturating_sub(window) / QUOTA_BUCKET_SECONDS
+}
+
+fn usage_in_window(
+    storage: &dyn Storage,
+    block_seconds: u64,
+    denom: &str,
+    address: &str,
+    window: u64,
+) -> StdResult<Uint128> {
+    let first = first_bucket_in_window(block_seconds, window);
+
+    QUOTA_USAGE
+        .prefix((denom, address))
+        .range(storage, Some(Bound::inclusive(first)), None, Order::Ascending)
+        .try_fold(Uint128::zero(), |used, bucket| Ok(used.checked_add(bucket?.1)?))
+}
+
+/// Amount the address can still send in the current window, or `None` if it has no quota.
+pub fn remaining_quota(
+    storage: &dyn Storage,
+    block_seconds: u64,
+    denom: &str,
+    address: &str,
+) -> StdResult<Option<Uint128>> {
+    let limit = match rate_limit_for(storage, &Addr::unchecked(address))? {
+        Some(limit) => limit,
+        None => return Ok(None),
+    };
+    let quota = match limit.quota {
+        Some(quota) => quota,
+        None => return Ok(None),
+    };
+
+    let supply = TOTAL_SUPPLY_HISTORY
+        .may_load(storage, denom)?
+        .unwrap_or_default();
+    let used = usage_in_window(storage, block_seconds, denom, address, limit.window)?;
+
+    Ok(Some(supply.mul_floor(quota).saturating_sub(used)))
+}
+
+/// Rejects sends above the sender's per-transaction max or remaining quota, and
+/// counts the send against its window. Mints and burns are never limited.
+pub fn consume_quota(
+    storage: &mut dyn Storage,
+    block_seconds: u64,
+    config: &DenomConfig,
+    from: &str,
+    to: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if from == config.module_address || to == config.module_address {
+        return Ok(());
+    }
+
+    let limit = match rate_limit_for(storage, &Addr::unchecked(from))? {
+        Some(limit) => limit,
+        None => return Ok(()),
+    };
+
+    if let Some(max) = limit.max_per_tx {
+        if amount > max {
+            return Err(ContractError::TransferLimitExceeded { max });
+        }
+    }
+
+    if let Some(remaining) = remaining_quota(storage, block_seconds, &config.tracked_denom, from)? {
+        if amount > remaining {
+            return Err(ContractError::QuotaExceeded { remaining });
+        }
+
+        // Buckets that fell out of the window are dropped as the address sends
+        let first = first_bucket_in_window(block_seconds, limit.window);
+        let expired = QUOTA_USAGE
+            .prefix((&config.tracked_denom, from))
+            .keys(storage, None, Some(Bound::exclusive(first)), Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for bucket in expired {
+            QUOTA_USAGE.remove(storage, (&config.tracked_denom, from, bucket));
+        }
+
+        let bucket = block_seconds / QUOTA_BUCKET_SECONDS;
+        QUOTA_USAGE.update::<_, StdError>(storage, (&config.tracked_denom, from, bucket), |used| {
+            Ok(used.unwrap_or_default().checked_add(amount)?)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Rejects the send if it would push the receiver's balance above the wallet
+/// cap in effect. Burns, excluded wallets and exempt addresses are not capped.
+pub fn check_wallet_cap(
+    storage: &dyn Storage,
+    block_seconds: u64,
+    config: &DenomConfig,
+    to: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let to_addr = Addr::unchecked(to);
+    if to == config.module_address
+        || EXCLUDED_WALLETS.may_load(storage, &to_addr)?.is_some()
+        || WALLET_CAP_EXEMPT.has(storage, &to_addr)
+    {
+        return Ok(());
+    }
+
+    let cap = match WALLET_CAPS
+        .may_load(storage, &config.tracked_denom)?
+        .and_then(|cap| cap.max_balance_at(block_seconds))
+    {
+        Some(cap) => cap,
+        None => return Ok(()),
+    };
+
+    let balance = BALANCES
+        .may_load(storage, (&config.tracked_denom, to))?
+        .unwrap_or_default();
+    if balance.saturating_add(amount) > cap {
+        return Err(ContractError::WalletCapExceeded { cap });
+    }
+
+    Ok(())
+}
+
+/// Rejects mints that push the on-chain supply above the denom's max supply.
+/// The bank module has already added the minted coins to its supply by the
+/// time the hook sees the send, so tokens minted before the denom was
+/// registered count toward the cap too.
+pub fn check_max_supply(
+    querier: &QuerierWrapper,
+    config: &DenomConfig,
+    from: &str,
+    to: &str,
+) -> Result<(), ContractError> {
+    let max_supply = match config.max_supply {
+        Some(max_supply) if from == config.module_address && to != config.module_address => {
+            max_supply
+        }
+        _ => return Ok(()),
+    };
+
+    let supply = querier.query_supply(&config.tracked_denom)?.amount;
+    if supply > max_supply {
+        return Err(ContractError::MaxSupplyExceeded { max_supply });
+    }
+
+    Ok(())
+}
+
+/// Rejects mints above what is left of the current epoch's allowance, and
+/// counts the mint against it. Mints before the schedule starts are not limited.
+pub fn consume_emission(
+    storage: &mut dyn Storage,
+    block_seconds: u64,
+    config: &DenomConfig,
+    from: &str,
+    to: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if from != config.module_address || to == config.module_address {
+        return Ok(());
+    }
+
+    let schedule = match EMISSION_SCHEDULES.may_load(storage, &config.tracked_denom)? {
+        Some(schedule) => schedule,
+        None => return Ok(()),
+    };
+    let epoch = match schedule.epoch_at(block_seconds) {
+        Some(epoch) => epoch,
+        None => return Ok(()),
+    };
+
+    let key = (config.tracked_denom.as_str(), epoch);
+    let minted = EPOCH_MINTED.may_load(storage, key)?.unwrap_or_default();
+    let remaining = schedule.allowance(epoch).saturating_sub(minted);
+    if amount > remaining {
+        return Err(ContractError::EmissionExceeded { epoch, remaining });
+    }
+
+    EPOCH_MINTED.save(storage, key, &(minted + amount))?;
+
+    Ok(())
+}
+
+/// Rejects transfers of a soulbound denom between users, unless the pair is allowed.
+pub fn check_transferable(
+    storage: &dyn Storage,
+    config: &DenomConfig,
+    from: &str,
+    to: &str,
+) -> Result<(), ContractError> {
+    if !config.soulbound || from == config.module_address || to == config.module_address {
+        return Ok(());
+    }
+
+    if SOULBOUND_PAIRS.has(storage, (&config.tracked_denom, from, to)) {
+        return Ok(());
+    }
+
+    Err(ContractError::Soulbound {
+        denom: config.tracked_denom.clone(),
+    })
+}
+
+/// Rejects sends that would drop the sender's balance below its locked amount.
+/// Excluded wallets are held to their bank balance instead.
+pub fn check_unlocked(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    block_seconds: u64,
+    config: &DenomConfig,
+    from: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if from == config.module_address {
+        return Ok(());
+    }
+
+    let locked = match VESTING.may_load(storage, (&config.tracked_denom, from))? {
+        Some(schedule) => schedule.locked_at(block_seconds),
+        None => return Ok(()),
+    };
+
+    let balance = held_balance(storage, querier, &config.tracked_denom, from)?;
+    if balance.saturating_sub(amount) < locked {
+        return Err(ContractError::BalanceLocked { locked });
+    }
+
+    Ok(())
+}
@@ -1,32 +1,209 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_json_binary, Binary, Deps, Env, StdResult, Uint128};
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Deps, Env, Order, StdError, StdResult, Uint128, Uint256,
+};
+use cw_paginate::{paginate_map_values, paginate_prefix_keys, paginate_snapshot_map};
+use cw_storage_plus::{Bound, SnapshotMap};
 
-use cw_paginate::paginate_snapshot_map_keys;
-use crate::state::{BALANCES, CONFIG, TOTAL_SUPPLY_HISTORY, STATE};
-use crate::msg::{HolderBalanceResponse, ListHoldersResponse, QueryMsg, ConfigResponse, ExcludedWalletsResponse};
+use crate::functions::balance_drift;
+use crate::policy::{rate_limit_for, remaining_quota};
+use crate::msg::{
+    AnomaliesResponse, BalanceDriftResponse, BlockedAddressResponse, BlockedAddressesResponse,
+    BurnerResponse, CategoriesResponse, CategoryMembersResponse, ConfigResponse, DriftResponse, EmissionStatusResponse, ExcludedWalletResponse,
+    ExcludedWalletsResponse, ExclusionEventsResponse, HolderBalanceResponse, ListHoldersResponse, PauseStatusResponse, QueryMsg, RemainingQuotaResponse, RolesResponse,
+    SoulboundPairResponse, SoulboundPairsResponse, SupplyBreakdownResponse, TopBurnersResponse, TrackFailuresResponse,
+    TrackedDenomResponse, TransfersResponse, WalletCapResponse,
+};
+use crate::state::{
+    ADDRESS_TRANSFERS, ANOMALIES, BALANCES, BALANCE_TWAB, BLOCKED, BURNED, BURN_RANKING, CATEGORIES,
+    CATEGORY_MEMBERS, CATEGORY_SUPPLY, CONFIG, DENOMS,
+    EMISSION_SCHEDULES, EPOCH_MINTED, EXCLUDED_SUPPLY_HISTORY, EXCLUDED_WALLETS, EXCLUSION_EVENTS, IMPORT_SEALED, PAUSED, PAUSE_EXEMPT,
+    PENDING_ADMIN, ROLES, SOULBOUND_PAIRS, TOTAL_BURNED_HISTORY, TOTAL_MINTED_HISTORY, TOTAL_SUPPLY_HISTORY,
+    TOTAL_SUPPLY_TWAB, TRACKED_SUPPLY, TRACK_FAILURES, TRANSFERS, VESTING, WALLET_CAPS,
+};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::BalanceAt { address, timestamp } => {
-            to_json_binary(&balance_at(deps, env, address, timestamp)?)
+        QueryMsg::BalanceAt { denom, address, timestamp } => {
+            to_json_binary(&balance_at(deps, env, denom, address, timestamp)?)
         }
-        QueryMsg::TotalSupplyAt { timestamp } => {
-            to_json_binary(&total_supply_at(deps, env, timestamp)?)
+        QueryMsg::TotalSupplyAt { denom, timestamp } => {
+            to_json_binary(&total_supply_at(deps, env, denom, timestamp)?)
         }
-        QueryMsg::GetHolders { from, limit, timestamp } => {
-            to_json_binary(&query_list_holders(deps, env, from, limit, timestamp)?)
+        QueryMsg::GetHolders { denom, from, limit, timestamp } => {
+            to_json_binary(&query_list_holders(deps, env, denom, from, limit, timestamp)?)
         }
         QueryMsg::Config {} => {
             let config = CONFIG.load(deps.storage)?;
+            let tracked_denoms = DENOMS
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| {
+                    let (_, config) = item?;
+                    let total_supply = TRACKED_SUPPLY
+                        .may_load(deps.storage, &config.tracked_denom)?
+                        .unwrap_or_default();
+                    let import_sealed = IMPORT_SEALED.has(deps.storage, &config.tracked_denom);
+                    Ok(TrackedDenomResponse {
+                        tracked_denom: config.tracked_denom,
+                        token_factory_module: config.module_address,
+                        max_supply: config.max_supply,
+                        soulbound: config.soulbound,
+                        lenient: config.lenient,
+                        import_sealed,
+                        total_supply,
+                    })
+                })
+                .collect::<StdResult<_>>()?;
             to_json_binary(&ConfigResponse {
-                tracked_denom: config.d,
-                token_factory_module: config.m,
+                admin_addr: config.admin_addr,
+                pending_admin: PENDING_ADMIN.may_load(deps.storage)?,
+                tracked_denoms,
+                hook_mode: config.hook_mode,
             })
         }
-        QueryMsg::GetExcludedWallets {} => {
-            to_json_binary(&query_excludedwallets(deps)?)
+        QueryMsg::GetExcludedWallets { start_after, limit } => {
+            to_json_binary(&query_excludedwallets(deps, start_after, limit)?)
+        }
+        QueryMsg::IsExcludedAt { address, timestamp } => {
+            to_json_binary(&is_excluded_at(deps, env, address, timestamp)?)
+        }
+        QueryMsg::ExclusionEvents { start_after, limit } => {
+            let events = paginate_map_values(
+                deps,
+                &EXCLUSION_EVENTS,
+                start_after,
+                limit.or(Some(10u32)),
+                Order::Ascending,
+            )?;
+            to_json_binary(&ExclusionEventsResponse { events })
+        }
+        QueryMsg::BurnedBy { denom, address, timestamp } => {
+            to_json_binary(&burned_by(deps, env, denom, address, timestamp)?)
+        }
+        QueryMsg::TotalBurnedAt { denom, timestamp } => {
+            to_json_binary(&total_burned_at(deps, env, denom, timestamp)?)
+        }
+        QueryMsg::TopBurners { denom, limit, start_after } => {
+            to_json_binary(&query_top_burners(deps, denom, limit, start_after)?)
+        }
+        QueryMsg::AverageBalance { denom, address, start, end } => {
+            to_json_binary(&average_balance(deps, env, denom, address, start, end)?)
+        }
+        QueryMsg::AverageTotalSupply { denom, start, end } => {
+            to_json_binary(&average_total_supply(deps, env, denom, start, end)?)
+        }
+        QueryMsg::PauseStatus {} => {
+            let exempt_addresses = PAUSE_EXEMPT
+                .keys(deps.storage, None, None, Order::Ascending)
+                .map(|address| Ok(address?.to_string()))
+                .collect::<StdResult<_>>()?;
+            to_json_binary(&PauseStatusResponse {
+                paused: PAUSED.may_load(deps.storage)?.unwrap_or_default(),
+                exempt_addresses,
+            })
+        }
+        QueryMsg::IsBlocked { address, timestamp } => {
+            to_json_binary(&is_blocked_at(deps, env, address, timestamp)?)
+        }
+        QueryMsg::BlockedAddresses { start_after, limit } => {
+            to_json_binary(&query_blocked_addresses(deps, start_after, limit)?)
+        }
+        QueryMsg::RemainingQuota { denom, address } => {
+            let block_seconds = env.block.time.seconds();
+            to_json_binary(&RemainingQuotaResponse {
+                limit: rate_limit_for(deps.storage, &Addr::unchecked(&address))?,
+                remaining: remaining_quota(deps.storage, block_seconds, &denom, &address)?,
+            })
+        }
+        QueryMsg::Categories {} => {
+            let categories = CATEGORIES
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<_>>()?;
+            to_json_binary(&CategoriesResponse { categories })
+        }
+        QueryMsg::CategoryMembers { category, start_after, limit } => {
+            let start_after = start_after.map(Addr::unchecked);
+            let addresses = paginate_prefix_keys(
+                deps,
+                &CATEGORY_MEMBERS.prefix(&category),
+                start_after.as_ref(),
+                limit.or(Some(10u32)),
+                Order::Ascending,
+            )?
+            .into_iter()
+            .map(|address| address.to_string())
+            .collect();
+            to_json_binary(&CategoryMembersResponse { addresses })
+        }
+        QueryMsg::CategorySupplyAt { denom, category, timestamp } => {
+            to_json_binary(&category_supply_at(deps, env, denom, category, timestamp)?)
+        }
+        QueryMsg::SupplyBreakdownAt { denom, timestamp } => {
+            to_json_binary(&supply_breakdown_at(deps, env, denom, timestamp)?)
+        }
+        QueryMsg::Drift { denom, addresses } => {
+            to_json_binary(&query_drift(deps, denom, addresses)?)
+        }
+        QueryMsg::Anomalies { start_after, limit } => {
+            let anomalies = paginate_map_values(
+                deps,
+                &ANOMALIES,
+                start_after,
+                limit.or(Some(10u32)),
+                Order::Ascending,
+            )?;
+            to_json_binary(&AnomaliesResponse { anomalies })
+        }
+        QueryMsg::TrackFailures { start_after, limit } => {
+            let failures = paginate_map_values(
+                deps,
+                &TRACK_FAILURES,
+                start_after,
+                limit.or(Some(10u32)),
+                Order::Ascending,
+            )?;
+            to_json_binary(&TrackFailuresResponse { failures })
+        }
+        QueryMsg::LockedBalance { denom, address, timestamp } => {
+            let locked = VESTING
+                .may_load(deps.storage, (&denom, &address))?
+                .map(|schedule| schedule.locked_at(timestamp.unwrap_or(env.block.time.seconds())))
+                .unwrap_or_default();
+            to_json_binary(&locked)
+        }
+        QueryMsg::SoulboundPairs { denom } => {
+            let pairs = SOULBOUND_PAIRS
+                .sub_prefix(&denom)
+                .keys(deps.storage, None, None, Order::Ascending)
+                .map(|pair| {
+                    let (from, to) = pair?;
+                    Ok(SoulboundPairResponse { from, to })
+                })
+                .collect::<StdResult<_>>()?;
+            to_json_binary(&SoulboundPairsResponse { pairs })
+        }
+        QueryMsg::EmissionStatus { denom } => {
+            to_json_binary(&query_emission_status(deps, env, denom)?)
+        }
+        QueryMsg::WalletCap { denom } => {
+            let cap = WALLET_CAPS.may_load(deps.storage, &denom)?;
+            let max_balance = cap
+                .as_ref()
+                .and_then(|cap| cap.max_balance_at(env.block.time.seconds()));
+            to_json_binary(&WalletCapResponse { cap, max_balance })
+        }
+        QueryMsg::Roles { address } => {
+            let roles = ROLES
+                .prefix(&Addr::unchecked(address))
+                .range(deps.storage, None, None, Order::Ascending)
+                .map(|item| Ok(item?.1))
+                .collect::<StdResult<_>>()?;
+            to_json_binary(&RolesResponse { roles })
+        }
+        QueryMsg::Transfers { start_after, limit, address } => {
+            to_json_binary(&query_transfers(deps, start_after, limit, address)?)
         }
     }
 }
@@ -34,52 +211,324 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
 pub fn query_list_holders(
     deps: Deps,
     env: Env,
+    denom: String,
     start_after: Option<String>,
     limit: Option<u32>,
     timestamp: Option<u64>,
 ) -> StdResult<ListHoldersResponse> {
 
-    let holders = paginate_snapshot_map_keys(
-        deps,
-        &BALANCES,
-        start_after.as_deref(),
-        limit.or(Some(10u32)),
-        cosmwasm_std::Order::Ascending,
-    )?;
+    let holders = BALANCES
+        .prefix(&denom)
+        .keys(
+            deps.storage,
+            start_after.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit.unwrap_or(10u32) as usize)
+        .collect::<StdResult<Vec<_>>>()?;
 
     let holders = holders
         .into_iter()
         .map(|address| HolderBalanceResponse {
             address: address.clone(),
-            balance: balance_at(deps, env.clone(), address, timestamp).unwrap_or(Uint128::zero()).into(),
+            balance: balance_at(deps, env.clone(), denom.clone(), address, timestamp).unwrap_or(Uint128::zero()).into(),
         })
         .collect();
 
     Ok(ListHoldersResponse { holders })
 }
 
-fn balance_at(deps: Deps, env: Env, address: String, timestamp: Option<u64>) -> StdResult<Uint128> {
+pub fn query_top_burners(
+    deps: Deps,
+    denom: String,
+    limit: Option<u32>,
+    start_after: Option<String>,
+) -> StdResult<TopBurnersResponse> {
+    // Resume below the position currently held by `start_after`
+    let start_after = start_after
+        .map(|address| -> StdResult<_> {
+            let burned = BURNED.may_load(deps.storage, (&denom, &address))?.unwrap_or_default();
+            Ok((burned.u128(), address))
+        })
+        .transpose()?;
+
+    let burners = BURN_RANKING
+        .sub_prefix(&denom)
+        .keys(
+            deps.storage,
+            None,
+            start_after
+                .as_ref()
+                .map(|(burned, address)| Bound::exclusive((*burned, address.as_str()))),
+            Order::Descending,
+        )
+        .take(limit.unwrap_or(10u32) as usize)
+        .map(|item| {
+            let (burned, address) = item?;
+            Ok(BurnerResponse {
+                address,
+                burned: Uint128::new(burned),
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(TopBurnersResponse { burners })
+}
+
+pub fn query_transfers(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+    address: Option<String>,
+) -> StdResult<TransfersResponse> {
+    let limit = limit.or(Some(10u32));
+
+    let transfers = match address {
+        Some(address) => paginate_prefix_keys(
+            deps,
+            &ADDRESS_TRANSFERS.prefix(&address),
+            start_after,
+            limit,
+            Order::Ascending,
+        )?
+        .into_iter()
+        .map(|id| TRANSFERS.load(deps.storage, id))
+        .collect::<StdResult<_>>()?,
+        None => paginate_map_values(deps, &TRANSFERS, start_after, limit, Order::Ascending)?,
+    };
+
+    Ok(TransfersResponse { transfers })
+}
+
+fn balance_at(deps: Deps, env: Env, denom: String, address: String, timestamp: Option<u64>) -> StdResult<Uint128> {
     let block_time = env.block.time.seconds();
     match timestamp.unwrap_or(block_time) {
-        timestamp if timestamp == block_time => BALANCES.may_load(deps.storage, &address),
-        timestamp => BALANCES.may_load_at_height(deps.storage, &address, timestamp),
+        timestamp if timestamp == block_time => BALANCES.may_load(deps.storage, (&denom, &address)),
+        timestamp => BALANCES.may_load_at_height(deps.storage, (&denom, &address), timestamp),
     }
     .map(|balance| balance.unwrap_or_default())
 }
 
-fn total_supply_at(deps: Deps, env: Env, timestamp: Option<u64>) -> StdResult<Uint128> {
+fn total_supply_at(deps: Deps, env: Env, denom: String, timestamp: Option<u64>) -> StdResult<Uint128> {
     let block_time = env.block.time.seconds();
     match timestamp.unwrap_or(block_time) {
-        timestamp if timestamp == block_time => TOTAL_SUPPLY_HISTORY.may_load(deps.storage),
-        timestamp => TOTAL_SUPPLY_HISTORY.may_load_at_height(deps.storage, timestamp),
+        timestamp if timestamp == block_time => TOTAL_SUPPLY_HISTORY.may_load(deps.storage, &denom),
+        timestamp => TOTAL_SUPPLY_HISTORY.may_load_at_height(deps.storage, &denom, timestamp),
     }
     .map(|total_supply| total_supply.unwrap_or_default())
 }
 
-fn query_excludedwallets(deps: Deps) -> StdResult<ExcludedWalletsResponse> {
-    let state = STATE.load(deps.storage)?;
+fn category_supply_at(
+    deps: Deps,
+    env: Env,
+    denom: String,
+    category: String,
+    timestamp: Option<u64>,
+) -> StdResult<Uint128> {
+    let block_time = env.block.time.seconds();
+    match timestamp.unwrap_or(block_time) {
+        timestamp if timestamp == block_time => CATEGORY_SUPPLY.may_load(deps.storage, (&denom, &category)),
+        timestamp => CATEGORY_SUPPLY.may_load_at_height(deps.storage, (&denom, &category), timestamp),
+    }
+    .map(|supply| supply.unwrap_or_default())
+}
 
-    Ok(ExcludedWalletsResponse {
-        excludedwallets: state.excluded_wallets.clone(),
+fn supply_breakdown_at(
+    deps: Deps,
+    env: Env,
+    denom: String,
+    timestamp: Option<u64>,
+) -> StdResult<SupplyBreakdownResponse> {
+    let block_time = env.block.time.seconds();
+    let timestamp = timestamp.unwrap_or(block_time);
+    let series_at = |series: &SnapshotMap<&str, Uint128>| -> StdResult<Uint128> {
+        match timestamp {
+            timestamp if timestamp == block_time => series.may_load(deps.storage, &denom),
+            timestamp => series.may_load_at_height(deps.storage, &denom, timestamp),
+        }
+        .map(|amount| amount.unwrap_or_default())
+    };
+
+    Ok(SupplyBreakdownResponse {
+        total: series_at(&TRACKED_SUPPLY)?,
+        circulating: series_at(&TOTAL_SUPPLY_HISTORY)?,
+        excluded: series_at(&EXCLUDED_SUPPLY_HISTORY)?,
+        minted: series_at(&TOTAL_MINTED_HISTORY)?,
+        burned: series_at(&TOTAL_BURNED_HISTORY)?,
     })
+}
+
+fn burned_by(deps: Deps, env: Env, denom: String, address: String, timestamp: Option<u64>) -> StdResult<Uint128> {
+    let block_time = env.block.time.seconds();
+    match timestamp.unwrap_or(block_time) {
+        timestamp if timestamp == block_time => BURNED.may_load(deps.storage, (&denom, &address)),
+        timestamp => BURNED.may_load_at_height(deps.storage, (&denom, &address), timestamp),
+    }
+    .map(|burned| burned.unwrap_or_default())
+}
+
+fn total_burned_at(deps: Deps, env: Env, denom: String, timestamp: Option<u64>) -> StdResult<Uint128> {
+    let block_time = env.block.time.seconds();
+    match timestamp.unwrap_or(block_time) {
+        timestamp if timestamp == block_time => TOTAL_BURNED_HISTORY.may_load(deps.storage, &denom),
+        timestamp => TOTAL_BURNED_HISTORY.may_load_at_height(deps.storage, &denom, timestamp),
+    }
+    .map(|total_burned| total_burned.unwrap_or_default())
+}
+
+fn average_balance(deps: Deps, env: Env, denom: String, address: String, start: u64, end: u64) -> StdResult<Uint128> {
+    let block_time = env.block.time.seconds();
+    let cumulative_at = |timestamp: u64| -> StdResult<Uint256> {
+        match timestamp {
+            timestamp if timestamp == block_time => BALANCE_TWAB.may_load(deps.storage, (&denom, &address)),
+            timestamp => BALANCE_TWAB.may_load_at_height(deps.storage, (&denom, &address), timestamp),
+        }?
+        .unwrap_or_default()
+        .cumulative_at(timestamp)
+    };
+
+    time_weighted_average(block_time, start, end, cumulative_at)
+}
+
+fn average_total_supply(deps: Deps, env: Env, denom: String, start: u64, end: u64) -> StdResult<Uint128> {
+    let block_time = env.block.time.seconds();
+    let cumulative_at = |timestamp: u64| -> StdResult<Uint256> {
+        match timestamp {
+            timestamp if timestamp == block_time => TOTAL_SUPPLY_TWAB.may_load(deps.storage, &denom),
+            timestamp => TOTAL_SUPPLY_TWAB.may_load_at_height(deps.storage, &denom, timestamp),
+        }?
+        .unwrap_or_default()
+        .cumulative_at(timestamp)
+    };
+
+    time_weighted_average(block_time, start, end, cumulative_at)
+}
+
+fn time_weighted_average(
+    block_time: u64,
+    start: u64,
+    end: u64,
+    cumulative_at: impl Fn(u64) -> StdResult<Uint256>,
+) -> StdResult<Uint128> {
+    if start >= end || end > block_time {
+        return Err(StdError::generic_err(format!(
+            "Invalid averaging window: start {start} end {end} block_seconds {block_time}"
+        )));
+    }
+
+    let accumulated = cumulative_at(end)?.checked_sub(cumulative_at(start)?)?;
+    let average = accumulated.checked_div(Uint256::from(end - start))?;
+
+    Ok(average.try_into()?)
+}
+
+fn is_blocked_at(deps: Deps, env: Env, address: String, timestamp: Option<u64>) -> StdResult<bool> {
+    let address = Addr::unchecked(address);
+    let block_time = env.block.time.seconds();
+    match timestamp.unwrap_or(block_time) {
+        timestamp if timestamp == block_time => BLOCKED.may_load(deps.storage, &address),
+        timestamp => BLOCKED.may_load_at_height(deps.storage, &address, timestamp),
+    }
+    .map(|reason| reason.is_some())
+}
+
+fn is_excluded_at(deps: Deps, env: Env, address: String, timestamp: Option<u64>) -> StdResult<bool> {
+    let address = Addr::unchecked(address);
+    let block_time = env.block.time.seconds();
+    match timestamp.unwrap_or(block_time) {
+        timestamp if timestamp == block_time => EXCLUDED_WALLETS.may_load(deps.storage, &address),
+        timestamp => EXCLUDED_WALLETS.may_load_at_height(deps.storage, &address, timestamp),
+    }
+    .map(|info| info.is_some())
+}
+
+fn query_blocked_addresses(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<BlockedAddressesResponse> {
+    let start_after = start_after.map(Addr::unchecked);
+
+    let blocked = paginate_snapshot_map(
+        deps,
+        &BLOCKED,
+        start_after.as_ref(),
+        limit.or(Some(10u32)),
+        Order::Ascending,
+    )?
+    .into_iter()
+    .map(|(address, reason)| BlockedAddressResponse {
+        address: address.to_string(),
+        reason,
+    })
+    .collect();
+
+    Ok(BlockedAddressesResponse { blocked })
+}
+
+fn query_drift(deps: Deps, denom: String, addresses: Vec<String>) -> StdResult<DriftResponse> {
+    let config = DENOMS.load(deps.storage, &denom)?;
+
+    let mut drifts = vec![];
+    for address in addresses {
+        let address = deps.api.addr_validate(&address)?;
+        if let Some((tracked, bank)) = balance_drift(deps, &config, &address)? {
+            drifts.push(BalanceDriftResponse {
+                address: address.to_string(),
+                tracked,
+                bank,
+            });
+        }
+    }
+
+    Ok(DriftResponse { drifts })
+}
+
+fn query_emission_status(deps: Deps, env: Env, denom: String) -> StdResult<EmissionStatusResponse> {
+    let schedule = EMISSION_SCHEDULES.may_load(deps.storage, &denom)?;
+    let epoch = schedule
+        .as_ref()
+        .and_then(|schedule| schedule.epoch_at(env.block.time.seconds()));
+
+    let (minted, remaining) = match (&schedule, epoch) {
+        (Some(schedule), Some(epoch)) => {
+            let minted = EPOCH_MINTED
+                .may_load(deps.storage, (&denom, epoch))?
+                .unwrap_or_default();
+            (minted, Some(schedule.allowance(epoch).saturating_sub(minted)))
+        }
+        _ => (Uint128::zero(), None),
+    };
+
+    Ok(EmissionStatusResponse {
+        schedule,
+        epoch,
+        minted,
+        remaining,
+    })
+}
+
+fn query_excludedwallets(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ExcludedWalletsResponse> {
+    let start_after = start_after.map(Addr::unchecked);
+
+    let excludedwallets = paginate_snapshot_map(
+        deps,
+        &EXCLUDED_WALLETS,
+        start_after.as_ref(),
+        limit.or(Some(10u32)),
+        Order::Ascending,
+    )?
+    .into_iter()
+    .map(|(address, info)| ExcludedWalletResponse {
+        address: address.to_string(),
+        memo: info.memo,
+    })
+    .collect();
+
+    Ok(ExcludedWalletsResponse { excludedwallets })
 }
\ No newline at end of file
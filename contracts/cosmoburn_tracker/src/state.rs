@@ -1,34 +1,446 @@
 
-use cosmwasm_std::Uint128;
-use cw_storage_plus::{Item, SnapshotItem, SnapshotMap, Strategy};
+use cosmwasm_std::{Addr, Decimal, StdResult, Uint128, Uint256};
+use cw_storage_plus::{Item, Map, SnapshotMap, Strategy};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 
 
+/// Legacy storage of excluded wallets, only read by migrations.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub excluded_wallets : HashMap<String, String>,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExclusionInfo {
+    pub memo: String,
+}
+
+/// Wallet being excluded from or included back into the supply.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExclusionEvent {
+    pub id: u64,
+    pub address: Addr,
+    pub excluded: bool,
+    /// Memo given on exclusion, `None` on inclusion
+    pub memo: Option<String>,
+    pub sender: Addr,
+    pub block_time: u64,
+    pub block_height: u64,
+}
+
+/// How a hooked send affected the tracked supply.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferKind {
+    Mint,
+    Burn,
+    Transfer,
+    ExcludedFlow,
+}
+
+/// A single journaled send of a tracked denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Transfer {
+    pub id: u64,
+    pub denom: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Uint128,
+    pub block_time: u64,
+    pub block_height: u64,
+    pub kind: TransferKind,
+}
+
+/// Running time-weighted balance accumulator.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Twab {
+    /// Balance since `last_update`
+    pub balance: Uint128,
+    /// Balance-seconds accumulated up to `last_update`
+    pub cumulative: Uint256,
+    pub last_update: u64,
+}
+
+impl Twab {
+    /// Starts accruing `balance` at `seconds`, with nothing accumulated before.
+    pub fn starting_at(seconds: u64, balance: Uint128) -> Twab {
+        Twab {
+            balance,
+            cumulative: Uint256::zero(),
+            last_update: seconds,
+        }
+    }
+
+    /// Returns the balance-seconds accumulated up to `seconds`.
+    pub fn cumulative_at(&self, seconds: u64) -> StdResult<Uint256> {
+        let elapsed = seconds.saturating_sub(self.last_update);
+        Ok(self
+            .cumulative
+            .checked_add(Uint256::from(self.balance).checked_mul(Uint256::from(elapsed))?)?)
+    }
+
+    /// Rolls the accumulator forward to `seconds` and starts accruing `balance`.
+    pub fn advance(&self, seconds: u64, balance: Uint128) -> StdResult<Twab> {
+        Ok(Twab {
+            balance,
+            cumulative: self.cumulative_at(seconds)?,
+            last_update: seconds,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub d: String,
-    pub m: String,
-    pub admin_addr: String,
+    /// Current admin, `None` once the admin role has been renounced
+    pub admin_addr: Option<String>,
+    #[serde(default)]
+    pub hook_mode: HookMode,
+}
+
+/// Which before-send hook runs the accounting.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HookMode {
+    /// Accounting runs in `BlockBeforeSend`, so its failures block the send
+    #[default]
+    Block,
+    /// Accounting runs in `TrackBeforeSend` and its failures are recorded,
+    /// leaving only policy checks in `BlockBeforeSend`
+    Track,
+}
+
+impl HookMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HookMode::Block => "block",
+            HookMode::Track => "track",
+        }
+    }
+}
+
+/// Send that underflowed the sender's tracked balance in lenient mode.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Anomaly {
+    pub id: u64,
+    pub denom: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Uint128,
+    /// Part of the amount the tracker didn't know the sender held, added to
+    /// the circulating supply
+    pub shortfall: Uint128,
+    /// Balance the tracker had on record, clamped to zero
+    pub tracked: Uint128,
+    pub block_time: u64,
+    pub block_height: u64,
+}
+
+/// Send whose accounting failed in track mode.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TrackFailure {
+    pub id: u64,
+    pub denom: String,
+    pub from: String,
+    pub to: String,
+    pub amount: Uint128,
+    pub block_time: u64,
+    pub block_height: u64,
+    pub error: String,
+}
+
+/// Named permissions the admin can grant to operators.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    ExclusionManager,
+    PolicyManager,
+    Pauser,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::ExclusionManager => "exclusion_manager",
+            Role::PolicyManager => "policy_manager",
+            Role::Pauser => "pauser",
+        }
+    }
+}
+
+/// Limits on how much an address can send of a tracked denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RateLimit {
+    /// Largest amount a single send can move
+    pub max_per_tx: Option<Uint128>,
+    /// Share of the denom's total supply an address can send per window
+    pub quota: Option<Decimal>,
+    /// Length of the rolling quota window in seconds. Sends are counted by the
+    /// hour, so each stays in the window until the end of its hour.
+    pub window: u64,
+}
+
+/// Largest balance an address can hold of a tracked denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WalletCap {
+    pub max_balance: Uint128,
+    /// Later changes to the cap, ordered by start time
+    pub schedule: Vec<WalletCapStep>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct WalletCapStep {
+    /// Block time in seconds from which the step applies
+    pub starts_at: u64,
+    /// New cap, or none to lift it
+    pub max_balance: Option<Uint128>,
+}
+
+impl WalletCap {
+    /// Cap in effect at the given block time, if any.
+    pub fn max_balance_at(&self, seconds: u64) -> Option<Uint128> {
+        self.schedule
+            .iter()
+            .rev()
+            .find(|step| step.starts_at <= seconds)
+            .map_or(Some(self.max_balance), |step| step.max_balance)
+    }
+}
+
+/// Per-epoch limit on how much of a tracked denom can be minted.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EmissionSchedule {
+    /// Block time in seconds at which the first epoch starts
+    pub start: u64,
+    /// Length of an epoch in seconds
+    pub epoch_length: u64,
+    /// Amount that can be minted during the first epoch
+    pub initial_allowance: Uint128,
+    /// Share of the allowance cut at each new epoch, 0.5 halves it
+    pub decay: Decimal,
+}
+
+impl EmissionSchedule {
+    /// Epoch the given block time falls into, if the schedule has started.
+    pub fn epoch_at(&self, seconds: u64) -> Option<u64> {
+        seconds
+            .checked_sub(self.start)
+            .map(|elapsed| elapsed / self.epoch_length)
+    }
+
+    /// Amount that can be minted during the given epoch, computed in closed
+    /// form so the cost doesn't grow with the number of elapsed epochs.
+    pub fn allowance(&self, epoch: u64) -> Uint128 {
+        let retained = Decimal::one() - self.decay;
+        let factor = match u32::try_from(epoch) {
+            Ok(epoch) => retained.checked_pow(epoch).unwrap_or_default(),
+            // Only a schedule without decay has anything left this far out
+            Err(_) if retained == Decimal::one() => Decimal::one(),
+            Err(_) => Decimal::zero(),
+        };
+        self.initial_allowance.mul_floor(factor)
+    }
+}
+
+/// Cliff plus linear vesting of a holder's balance.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingSchedule {
+    /// Amount locked at the start
+    pub total: Uint128,
+    /// Block time in seconds from which the amount vests linearly
+    pub start: u64,
+    /// Block time in seconds before which nothing is unlocked
+    pub cliff: u64,
+    /// Block time in seconds at which everything is unlocked
+    pub end: u64,
+}
+
+impl VestingSchedule {
+    /// Amount still locked at the given block time.
+    pub fn locked_at(&self, seconds: u64) -> Uint128 {
+        if seconds < self.cliff {
+            return self.total;
+        }
+        if seconds >= self.end {
+            return Uint128::zero();
+        }
+
+        let vested = self
+            .total
+            .multiply_ratio(seconds - self.start, self.end - self.start);
+        self.total - vested
+    }
+}
+
+/// Admin proposed by the current admin, awaiting acceptance.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingAdmin {
+    pub addr: String,
+    /// Block time in seconds after which the proposal can no longer be accepted
+    pub expires_at: Option<u64>,
+}
+
+/// Tracking parameters of a registered tokenfactory denom.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DenomConfig {
+    pub tracked_denom: String,
+    /// Token factory module address, the sender of mints and receiver of burns
+    pub module_address: String,
+    /// Cap on the minted supply; it can only be lowered once set
+    pub max_supply: Option<Uint128>,
+    /// Whether the denom can only be minted and burned
+    #[serde(default)]
+    pub soulbound: bool,
+    /// Whether sender underflows are clamped and recorded instead of failing the send
+    #[serde(default)]
+    pub lenient: bool,
 }
 
 
 pub const CONFIG: Item<Config> = Item::new("c");
 
+pub const PENDING_ADMIN: Item<PendingAdmin> = Item::new("pa");
+
+/// Roles granted by the admin, keyed by (address, role).
+pub const ROLES: Map<(&Addr, &str), Role> = Map::new("r");
+
+/// Registered denoms, keyed by denom.
+pub const DENOMS: Map<&str, DenomConfig> = Map::new("d");
+
 pub const STATE: Item<State> = Item::new("state");
 
+/// Wallets that aren't tracked. Their holdings are left out of `BALANCES` and
+/// the circulating supply and counted in the excluded supply instead.
+/// Snapshotted so past exclusions can be audited.
+pub const EXCLUDED_WALLETS: SnapshotMap<&Addr, ExclusionInfo> =
+    SnapshotMap::new("ex", "ex_chpts", "ex_chlg", Strategy::EveryBlock);
+
+/// Sequence id of the last exclusion event.
+pub const EXCLUSION_EVENT_SEQ: Item<u64> = Item::new("xe_seq");
+
+/// Wallet exclusions and inclusions, keyed by sequence id.
+pub const EXCLUSION_EVENTS: Map<u64, ExclusionEvent> = Map::new("xe");
 
-/// Contains snapshotted balances at every block.
-pub const BALANCES: SnapshotMap<&str, Uint128> =
+
+/// Contains snapshotted balances at every block, keyed by (denom, address).
+/// 0.1.0 keyed the same namespace by address alone; `migrate_balances`
+/// re-keys those records under the denom they were tracked for.
+pub const BALANCES: SnapshotMap<(&str, &str), Uint128> =
     SnapshotMap::new("b", "b_chpts", "b_chlg", Strategy::EveryBlock);
 
-/// Contains the history of the total supply of the tracked denom
-pub const TOTAL_SUPPLY_HISTORY: SnapshotItem<Uint128> =
-    SnapshotItem::new("t", "t_chpts", "t_chlg", Strategy::EveryBlock);
+/// Contains the history of the circulating supply of each tracked denom,
+/// leaving out what excluded wallets hold. 0.1.0 stored a single series under
+/// the same namespace, re-keyed by `migrate_balances`.
+pub const TOTAL_SUPPLY_HISTORY: SnapshotMap<&str, Uint128> =
+    SnapshotMap::new("t", "t_chpts", "t_chlg", Strategy::EveryBlock);
+
+/// Cumulative amount burned by each address, keyed by (denom, address).
+pub const BURNED: SnapshotMap<(&str, &str), Uint128> =
+    SnapshotMap::new("br", "br_chpts", "br_chlg", Strategy::EveryBlock);
+
+/// Contains the history of the cumulative amount burned of each tracked denom
+pub const TOTAL_BURNED_HISTORY: SnapshotMap<&str, Uint128> =
+    SnapshotMap::new("tb", "tb_chpts", "tb_chlg", Strategy::EveryBlock);
+
+/// Secondary index of `BURNED` ordered by amount, keyed by (denom, burned, address).
+pub const BURN_RANKING: Map<(&str, u128, &str), ()> = Map::new("brr");
+
+/// Sequence id of the last journaled transfer.
+pub const TRANSFER_SEQ: Item<u64> = Item::new("tr_seq");
+
+/// Append-only journal of every hooked send, keyed by sequence id.
+pub const TRANSFERS: Map<u64, Transfer> = Map::new("tr");
+
+/// Index of `TRANSFERS` by sender and receiver, keyed by (address, sequence id).
+pub const ADDRESS_TRANSFERS: Map<(&str, u64), ()> = Map::new("tr_addr");
+
+/// Time-weighted balance accumulators, keyed by (denom, address).
+pub const BALANCE_TWAB: SnapshotMap<(&str, &str), Twab> =
+    SnapshotMap::new("bw", "bw_chpts", "bw_chlg", Strategy::EveryBlock);
+
+/// Time-weighted total supply accumulators, keyed by denom.
+pub const TOTAL_SUPPLY_TWAB: SnapshotMap<&str, Twab> =
+    SnapshotMap::new("tw", "tw_chpts", "tw_chlg", Strategy::EveryBlock);
+
+/// Whether sends of tracked denoms are currently rejected.
+pub const PAUSED: Item<bool> = Item::new("p");
+
+/// Addresses whose sends and receipts go through while paused.
+pub const PAUSE_EXEMPT: Map<&Addr, ()> = Map::new("p_ex");
+
+/// Frozen addresses and the reason they were blocked, snapshotted at every block.
+pub const BLOCKED: SnapshotMap<&Addr, String> =
+    SnapshotMap::new("bl", "bl_chpts", "bl_chlg", Strategy::EveryBlock);
+
+/// Rate limit applied to every address without its own.
+pub const RATE_LIMIT: Item<RateLimit> = Item::new("rl");
+
+/// Per-address rate limits, overriding `RATE_LIMIT`.
+pub const ADDRESS_RATE_LIMITS: Map<&Addr, RateLimit> = Map::new("rl_addr");
+
+/// Addresses whose sends are never rate limited.
+pub const RATE_LIMIT_EXEMPT: Map<&Addr, ()> = Map::new("rl_ex");
+
+/// Amount sent against the quota in each hour, keyed by (denom, address, hour
+/// since the epoch).
+pub const QUOTA_USAGE: Map<(&str, &str, u64), Uint128> = Map::new("rl_use");
+
+/// Wallet balance caps, keyed by denom.
+pub const WALLET_CAPS: Map<&str, WalletCap> = Map::new("wc");
+
+/// Pools and other addresses that can receive past the wallet cap.
+pub const WALLET_CAP_EXEMPT: Map<&Addr, ()> = Map::new("wc_ex");
+
+/// Total supply, the circulating plus the excluded supply, keyed by denom.
+pub const TRACKED_SUPPLY: SnapshotMap<&str, Uint128> =
+    SnapshotMap::new("ms", "ms_chpts", "ms_chlg", Strategy::EveryBlock);
+
+/// History of the supply held by excluded wallets, keyed by denom.
+pub const EXCLUDED_SUPPLY_HISTORY: SnapshotMap<&str, Uint128> =
+    SnapshotMap::new("xs", "xs_chpts", "xs_chlg", Strategy::EveryBlock);
+
+/// History of the cumulative amount minted, keyed by denom.
+pub const TOTAL_MINTED_HISTORY: SnapshotMap<&str, Uint128> =
+    SnapshotMap::new("tm", "tm_chpts", "tm_chlg", Strategy::EveryBlock);
+
+/// Emission schedules, keyed by denom.
+pub const EMISSION_SCHEDULES: Map<&str, EmissionSchedule> = Map::new("em");
+
+/// Amount minted during each epoch of the emission schedule, keyed by (denom, epoch).
+pub const EPOCH_MINTED: Map<(&str, u64), Uint128> = Map::new("em_m");
+
+/// Sender and receiver pairs allowed to transfer a soulbound denom, keyed by (denom, from, to).
+pub const SOULBOUND_PAIRS: Map<(&str, &str, &str), ()> = Map::new("sb_pairs");
+
+/// Vesting schedules, keyed by (denom, address).
+pub const VESTING: Map<(&str, &str), VestingSchedule> = Map::new("vs");
+
+/// Sequence id of the last recorded track failure.
+pub const TRACK_FAILURE_SEQ: Item<u64> = Item::new("tf_seq");
+
+/// Sends whose accounting failed in track mode, keyed by sequence id.
+pub const TRACK_FAILURES: Map<u64, TrackFailure> = Map::new("tf");
+
+/// Sequence id of the last recorded anomaly.
+pub const ANOMALY_SEQ: Item<u64> = Item::new("an_seq");
+
+/// Balance underflows clamped in lenient mode, keyed by sequence id.
+pub const ANOMALIES: Map<u64, Anomaly> = Map::new("an");
+
+/// Denoms whose balance import is sealed.
+pub const IMPORT_SEALED: Map<&str, ()> = Map::new("imp_sealed");
+
+/// Admin-defined address categories.
+pub const CATEGORIES: Map<&str, ()> = Map::new("cat");
+
+/// Category of each categorized address.
+pub const ADDRESS_CATEGORY: Map<&Addr, String> = Map::new("cat_addr");
+
+/// Addresses of each category, keyed by (category, address).
+pub const CATEGORY_MEMBERS: Map<(&str, &Addr), ()> = Map::new("cat_mem");
+
+/// History of the balance held by each category, keyed by (denom, category).
+pub const CATEGORY_SUPPLY: SnapshotMap<(&str, &str), Uint128> =
+    SnapshotMap::new("cs", "cs_chpts", "cs_chlg", Strategy::EveryBlock);
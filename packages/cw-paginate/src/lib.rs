@@ -42,7 +42,9 @@
 use cosmwasm_std::{Deps, Order, StdResult};
 
 #[allow(unused_imports)]
-use cw_storage_plus::{Bound, Bounder, KeyDeserialize, Map, SnapshotMap, Strategy};
+use cw_storage_plus::{
+    Bound, Bounder, KeyDeserialize, Map, Prefix, PrimaryKey, SnapshotMap, Strategy,
+};
 
 /// Generic function for paginating a list of (K, V) pairs in a
 /// CosmWasm Map.
@@ -179,6 +181,35 @@ where
     }
 }
 
+/// Same as `paginate_map_keys` but for use with a `Prefix` of a map
+/// with a composite key.
+pub fn paginate_prefix_keys<'a, K, V, B>(
+    deps: Deps,
+    prefix: &Prefix<K, V, B>,
+    start_after: Option<B>,
+    limit: Option<u32>,
+    order: Order,
+) -> StdResult<Vec<K::Output>>
+where
+    K: KeyDeserialize,
+    K::Output: 'static,
+    V: serde::de::DeserializeOwned + serde::Serialize,
+    B: PrimaryKey<'a>,
+{
+    let (range_min, range_max) = match order {
+        Order::Ascending => (start_after.map(Bound::exclusive), None),
+        Order::Descending => (None, start_after.map(Bound::exclusive)),
+    };
+
+    let items = prefix.keys(deps.storage, range_min, range_max, order);
+    match limit {
+        Some(limit) => Ok(items
+            .take(limit.try_into().unwrap())
+            .collect::<StdResult<_>>()?),
+        None => Ok(items.collect::<StdResult<_>>()?),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -466,6 +497,47 @@ mod tests {
         assert_eq!(items, vec![]);
     }
 
+    #[test]
+    fn prefix_key_pagination() {
+        let mut deps = mock_dependencies();
+        let map: Map<(&str, u64), ()> = Map::new("items");
+
+        for num in 1u64..=10 {
+            map.save(&mut deps.storage, ("even", num * 2), &()).unwrap();
+            map.save(&mut deps.storage, ("odd", num * 2 - 1), &()).unwrap();
+        }
+
+        let items = paginate_prefix_keys(
+            deps.as_ref(),
+            &map.prefix("even"),
+            None,
+            Some(3),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(items, vec![2, 4, 6]);
+
+        let items = paginate_prefix_keys(
+            deps.as_ref(),
+            &map.prefix("odd"),
+            Some(7),
+            Some(3),
+            Order::Ascending,
+        )
+        .unwrap();
+        assert_eq!(items, vec![9, 11, 13]);
+
+        let items = paginate_prefix_keys(
+            deps.as_ref(),
+            &map.prefix("odd"),
+            Some(7),
+            None,
+            Order::Descending,
+        )
+        .unwrap();
+        assert_eq!(items, vec![5, 3, 1]);
+    }
+
     /// testing reworked paginate_map and paginate_map_keys.
     /// pay particular attention to the values added. this is to ensure
     /// that the values arent being assessed